@@ -0,0 +1,145 @@
+use clap::{Args, ValueEnum};
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, AlcovReader, Error, ModOffFields};
+use clap_stdin::FileOrStdin;
+
+use crate::convert::script::{write_ghidra_script, write_ida_script};
+#[cfg(feature = "parquet")]
+use crate::convert::table::parquet::ParquetTables;
+use crate::convert::table::{TableWriter, TextFormat, TextTables};
+
+pub mod script;
+pub mod table;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ConvertFormat {
+    /// "module+offset" text, one block per line
+    Modoff,
+    /// Ghidra Python script colouring the blocks of a module
+    GhidraScript,
+    /// IDAPython script colouring the blocks of a module
+    IdaScript,
+    /// CSV tables of modules, blocks and edges, in the output directory
+    Csv,
+    /// JSON Lines tables of modules, blocks and edges, in the output directory
+    Jsonl,
+    /// Apache Parquet tables of modules, blocks and edges, in the output directory
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ConvertFormat {
+    /// Tables are written in a directory, streaming the input.
+    fn is_tabular(self) -> bool {
+        match self {
+            Self::Csv | Self::Jsonl => true,
+            #[cfg(feature = "parquet")]
+            Self::Parquet => true,
+            Self::Modoff | Self::GhidraScript | Self::IdaScript => false,
+        }
+    }
+}
+
+/// Convert an alcov file to another format
+#[derive(Clone, Debug, Args)]
+pub struct Convert {
+    /// Output format
+    #[arg(short, long)]
+    pub to: ConvertFormat,
+    /// Output file, or empty to write to STDOUT. Output directory for tables
+    #[arg(
+        short,
+        long,
+        required_if_eq_any([("to", "csv"), ("to", "jsonl"), ("to", "parquet")])
+    )]
+    pub output: Option<PathBuf>,
+    /// Write the size of blocks (modoff)
+    #[arg(short, long)]
+    pub size: bool,
+    /// Write the size and number of hits of blocks (modoff)
+    #[arg(short = 'n', long)]
+    pub count: bool,
+    /// Module to export, by path or file name (ghidra-script, ida-script)
+    #[arg(
+        short,
+        long,
+        required_if_eq_any([("to", "ghidra-script"), ("to", "ida-script")])
+    )]
+    pub module: Option<PathBuf>,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+/// Find a module by path, or by file name.
+fn find_module(alcov: &Alcov, wanted: &Path) -> Result<u16, Error> {
+    let by_name = wanted.components().count() == 1;
+
+    let module_id = alcov
+        .modules
+        .iter()
+        .position(|module| {
+            module.path.as_deref().is_some_and(|path| {
+                path == wanted || (by_name && path.file_name() == Some(wanted.as_os_str()))
+            })
+        })
+        .ok_or_else(|| Error::ModuleNotFound(wanted.to_path_buf()))?;
+
+    Ok(u16::try_from(module_id)?)
+}
+
+impl Convert {
+    pub fn run(self) -> Result<(), Error> {
+        let mut input_rdr = self.input.into_reader().unwrap();
+
+        if self.to.is_tabular() {
+            // required by clap for tables.
+            let dir = self.output.as_deref().unwrap();
+
+            let tables: Box<dyn TableWriter> = match self.to {
+                ConvertFormat::Jsonl => Box::new(TextTables::create(dir, TextFormat::Jsonl)?),
+                #[cfg(feature = "parquet")]
+                ConvertFormat::Parquet => Box::new(ParquetTables::create(dir)?),
+                _ => Box::new(TextTables::create(dir, TextFormat::Csv)?),
+            };
+
+            return table::export(AlcovReader::new(input_rdr)?, tables);
+        }
+
+        let alcov = Alcov::read(&mut input_rdr)?;
+
+        let mut writer: BufWriter<Box<dyn Write>> = match &self.output {
+            Some(output) => BufWriter::new(Box::new(File::create(output)?)),
+            None => BufWriter::new(Box::new(io::stdout())),
+        };
+
+        // required by clap for the formats using it.
+        let module = self.module.as_deref();
+
+        match self.to {
+            ConvertFormat::Modoff => alcov.write_modoff(
+                &mut writer,
+                ModOffFields {
+                    size: self.size,
+                    count: self.count,
+                },
+            )?,
+            ConvertFormat::GhidraScript => {
+                write_ghidra_script(&mut writer, &alcov, find_module(&alcov, module.unwrap())?)?
+            }
+            ConvertFormat::IdaScript => {
+                write_ida_script(&mut writer, &alcov, find_module(&alcov, module.unwrap())?)?
+            }
+            _ => unreachable!("tables are streamed"),
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
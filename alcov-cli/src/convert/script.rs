@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, Error};
+
+/// Colour of the least executed blocks.
+const COLD: (u8, u8, u8) = (0xff, 0xf2, 0xb3);
+/// Colour of the most executed blocks.
+const HOT: (u8, u8, u8) = (0xff, 0x50, 0x40);
+
+/// Shared by the Ghidra and IDA scripts: coloured blocks, and the dynamic successors of
+/// each block.
+const SCRIPT_COMMON: &str = r#"
+def uncovered_targets(src, static_dsts):
+    """Static successors of a covered block never reached from it."""
+    if EDGES is None:
+        return [dst for dst in static_dsts if dst not in COVERED]
+    dynamic_dsts = EDGES.get(src, ())
+    return [dst for dst in static_dsts if dst not in dynamic_dsts]
+
+COVERED = set(block[0] for block in BLOCKS)
+"#;
+
+const GHIDRA_SCRIPT: &str = r#"
+# Run from the Ghidra Script Manager (Jython or PyGhidra).
+from java.awt import Color
+from ghidra.program.model.address import AddressSet
+from ghidra.program.model.block import BasicBlockModel
+
+image_base = currentProgram.getImageBase()
+model = BasicBlockModel(currentProgram)
+
+def address(offset):
+    return image_base.add(offset)
+
+for (offset, size, nb_taken, (r, g, b)) in BLOCKS:
+    start = address(offset)
+    end = address(offset + max(size, 1) - 1)
+
+    setBackgroundColor(AddressSet(start, end), Color(r, g, b))
+    setEOLComment(start, "alcov: taken %d times" % nb_taken)
+
+    for code_block in model.getCodeBlocksContaining(start, monitor):
+        static_dsts = []
+        dsts = code_block.getDestinations(monitor)
+        while dsts.hasNext():
+            dst = dsts.next()
+            if dst.getFlowType().isCall():
+                continue
+            static_dsts.append(dst.getDestinationAddress().subtract(image_base))
+
+        for dst in uncovered_targets(offset, static_dsts):
+            createBookmark(address(dst), "alcov", "uncovered branch target")
+
+print("alcov: %d blocks coloured" % len(BLOCKS))
+"#;
+
+const IDA_SCRIPT: &str = r#"
+# Run with File > Script file... in IDA.
+import ida_funcs
+import ida_gdl
+import ida_nalt
+import idautils
+import idc
+
+image_base = ida_nalt.get_imagebase()
+bookmark_slot = 0
+
+for (offset, size, nb_taken, (r, g, b)) in BLOCKS:
+    start = image_base + offset
+    end = start + max(size, 1)
+
+    for head in idautils.Heads(start, end):
+        idc.set_color(head, idc.CIC_ITEM, (b << 16) | (g << 8) | r)
+    idc.set_cmt(start, "alcov: taken %d times" % nb_taken, 0)
+
+    func = ida_funcs.get_func(start)
+    if func is None:
+        continue
+
+    for code_block in ida_gdl.FlowChart(func):
+        if not (code_block.start_ea <= start < code_block.end_ea):
+            continue
+
+        static_dsts = [succ.start_ea - image_base for succ in code_block.succs()]
+        for dst in uncovered_targets(offset, static_dsts):
+            # IDA only has 1024 bookmark slots.
+            if bookmark_slot < 1024:
+                idc.put_bookmark(image_base + dst, 0, 0, 0, bookmark_slot, "alcov: uncovered branch target")
+                bookmark_slot += 1
+
+print("alcov: %d blocks coloured" % len(BLOCKS))
+"#;
+
+/// Interpolate between the cold and hot colours, on a logarithmic scale.
+fn heat_colour(nb_taken: u64, max_taken: u64) -> (u8, u8, u8) {
+    let heat = if max_taken > 1 && nb_taken > 0 {
+        (nb_taken as f64).ln() / (max_taken as f64).ln()
+    } else {
+        0.0
+    };
+
+    let mix = |cold: u8, hot: u8| (cold as f64 + (hot as f64 - cold as f64) * heat).round() as u8;
+    (mix(COLD.0, HOT.0), mix(COLD.1, HOT.1), mix(COLD.2, HOT.2))
+}
+
+/// Write the blocks and edges of a module as Python data, with offsets from the module
+/// base address.
+fn write_data<W>(writer: &mut W, alcov: &Alcov, module_id: u16) -> Result<(), Error>
+where
+    W: Write,
+{
+    let module = &alcov.modules[module_id as usize];
+
+    // block ID to module offset.
+    let offsets: BTreeMap<usize, u64> = alcov
+        .blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, block)| block.module_id == module_id)
+        .filter_map(|(block_id, block)| {
+            let offset = module.module_offset(block.segment_id, block.segment_offset)?;
+            Some((block_id, offset))
+        })
+        .collect();
+
+    let max_taken = offsets
+        .keys()
+        .map(|block_id| alcov.blocks[*block_id].nb_taken)
+        .max()
+        .unwrap_or(0);
+
+    writeln!(writer, "BLOCKS = [")?;
+    for (block_id, offset) in &offsets {
+        let block = &alcov.blocks[*block_id];
+        writeln!(
+            writer,
+            "    ({:#x}, {:#x}, {}, {:?}),",
+            offset,
+            block.size,
+            block.nb_taken,
+            heat_colour(block.nb_taken, max_taken)
+        )?;
+    }
+    writeln!(writer, "]")?;
+    writeln!(writer)?;
+
+    let Some(edges) = &alcov.edges else {
+        writeln!(writer, "EDGES = None")?;
+        return Ok(());
+    };
+
+    writeln!(writer, "EDGES = {{")?;
+    for (block_id, offset) in &offsets {
+        let Some(block_edges) = edges.adj_list.get(*block_id) else {
+            continue;
+        };
+
+        let dsts: BTreeSet<u64> = block_edges
+            .dst_modules
+            .keys()
+            .filter_map(|dst| offsets.get(&(dst.dst_block_id as usize)).copied())
+            .collect();
+
+        if dsts.is_empty() {
+            continue;
+        }
+
+        let dsts: Vec<String> = dsts.iter().map(|dst| format!("{dst:#x}")).collect();
+        writeln!(writer, "    {:#x}: set([{}]),", offset, dsts.join(", "))?;
+    }
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+fn write_script<W>(writer: &mut W, alcov: &Alcov, module_id: u16, body: &str) -> Result<(), Error>
+where
+    W: Write,
+{
+    let path = alcov.modules[module_id as usize]
+        .path
+        .as_ref()
+        .map_or("<no path>".to_string(), |path| path.display().to_string());
+
+    writeln!(writer, "# Coverage of {path}, generated by alcov.")?;
+    writeln!(writer)?;
+    write_data(writer, alcov, module_id)?;
+    write!(writer, "{SCRIPT_COMMON}{body}")?;
+
+    Ok(())
+}
+
+/// Write a Ghidra Python script colouring the covered blocks of a module.
+pub fn write_ghidra_script<W>(writer: &mut W, alcov: &Alcov, module_id: u16) -> Result<(), Error>
+where
+    W: Write,
+{
+    write_script(writer, alcov, module_id, GHIDRA_SCRIPT)
+}
+
+/// Write an IDAPython script colouring the covered blocks of a module.
+pub fn write_ida_script<W>(writer: &mut W, alcov: &Alcov, module_id: u16) -> Result<(), Error>
+where
+    W: Write,
+{
+    write_script(writer, alcov, module_id, IDA_SCRIPT)
+}
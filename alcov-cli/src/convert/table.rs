@@ -0,0 +1,257 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovBlock, AlcovIsaMode, AlcovModule, AlcovReader, Error};
+
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// Writer of the modules, blocks and edges tables, one row at a time.
+pub trait TableWriter {
+    fn module(&mut self, module_id: u16, module: &AlcovModule) -> Result<(), Error>;
+
+    /// `address` is `None` if the block is outside of its module, and `mode` if neither
+    /// the block nor its module have a known instruction encoding.
+    fn block(
+        &mut self,
+        block_id: u64,
+        block: &AlcovBlock,
+        address: Option<u64>,
+        mode: Option<AlcovIsaMode>,
+    ) -> Result<(), Error>;
+
+    fn edge(&mut self, src: u64, dst: u64, nb_taken: u64) -> Result<(), Error>;
+
+    fn finish(self: Box<Self>) -> Result<(), Error>;
+}
+
+/// Segments of a module, as `start-end` module offsets separated by `;`.
+pub fn segments_str(module: &AlcovModule) -> String {
+    module
+        .segments
+        .iter()
+        .map(|segment| {
+            format!(
+                "{:#x}-{:#x}",
+                segment.module_range.start, segment.module_range.end
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+/// Stream every row of an alcov file to a table writer.
+pub fn export<R>(
+    mut alcov_rdr: AlcovReader<R>,
+    mut tables: Box<dyn TableWriter>,
+) -> Result<(), Error>
+where
+    R: Read,
+{
+    for (module_id, module) in alcov_rdr.modules.iter().enumerate() {
+        tables.module(u16::try_from(module_id)?, module)?;
+    }
+
+    let mut block_id: u64 = 0;
+    while let Some(block) = alcov_rdr.next_block()? {
+        let module = alcov_rdr.modules.get(block.module_id as usize);
+        let address = module.and_then(|module| {
+            let offset = module.module_offset(block.segment_id, block.segment_offset)?;
+            Some(module.base_address + offset)
+        });
+        let mode = module.and_then(|module| block.isa_mode(module));
+
+        tables.block(block_id, &block, address, mode)?;
+        block_id += 1;
+    }
+
+    while let Some((src, dst_edge, dst_edge_md)) = alcov_rdr.next_edge()? {
+        tables.edge(src, dst_edge.dst_block_id, dst_edge_md.nb_taken)?;
+    }
+
+    tables.finish()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    Csv,
+    Jsonl,
+}
+
+/// CSV or JSON Lines tables, in `modules`, `blocks` and `edges` files of a directory.
+pub struct TextTables {
+    format: TextFormat,
+    modules: BufWriter<File>,
+    blocks: BufWriter<File>,
+    edges: BufWriter<File>,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+    json.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+impl TextTables {
+    pub fn create(dir: &Path, format: TextFormat) -> Result<Self, Error> {
+        fs::create_dir_all(dir)?;
+
+        let extension = match format {
+            TextFormat::Csv => "csv",
+            TextFormat::Jsonl => "jsonl",
+        };
+        let create = |table: &str| -> Result<BufWriter<File>, Error> {
+            Ok(BufWriter::new(File::create(
+                dir.join(format!("{table}.{extension}")),
+            )?))
+        };
+
+        let mut tables = Self {
+            format,
+            modules: create("modules")?,
+            blocks: create("blocks")?,
+            edges: create("edges")?,
+        };
+
+        if format == TextFormat::Csv {
+            writeln!(tables.modules, "id,path,base,segments")?;
+            writeln!(
+                tables.blocks,
+                "id,module,segment,offset,address,size,nb_taken,mode,nb_instructions"
+            )?;
+            writeln!(tables.edges, "src,dst,nb_taken")?;
+        }
+
+        Ok(tables)
+    }
+}
+
+impl TableWriter for TextTables {
+    fn module(&mut self, module_id: u16, module: &AlcovModule) -> Result<(), Error> {
+        let path = module
+            .path
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned());
+
+        match self.format {
+            TextFormat::Csv => writeln!(
+                self.modules,
+                "{},{},{},{}",
+                module_id,
+                csv_field(path.as_deref().unwrap_or("")),
+                module.base_address,
+                segments_str(module)
+            )?,
+            TextFormat::Jsonl => {
+                let segments: Vec<String> = module
+                    .segments
+                    .iter()
+                    .map(|segment| {
+                        format!(
+                            "{{\"start\":{},\"end\":{}}}",
+                            segment.module_range.start, segment.module_range.end
+                        )
+                    })
+                    .collect();
+
+                writeln!(
+                    self.modules,
+                    "{{\"id\":{},\"path\":{},\"base\":{},\"segments\":[{}]}}",
+                    module_id,
+                    path.as_deref().map_or("null".to_string(), json_string),
+                    module.base_address,
+                    segments.join(",")
+                )?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn block(
+        &mut self,
+        block_id: u64,
+        block: &AlcovBlock,
+        address: Option<u64>,
+        mode: Option<AlcovIsaMode>,
+    ) -> Result<(), Error> {
+        match self.format {
+            TextFormat::Csv => writeln!(
+                self.blocks,
+                "{},{},{},{},{},{},{},{},{}",
+                block_id,
+                block.module_id,
+                block.segment_id,
+                block.segment_offset,
+                address.map_or(String::new(), |address| address.to_string()),
+                block.size,
+                block.nb_taken,
+                mode.map_or(String::new(), |mode| mode.to_string()),
+                block
+                    .nb_instructions
+                    .map_or(String::new(), |nb| nb.to_string())
+            )?,
+            TextFormat::Jsonl => writeln!(
+                self.blocks,
+                "{{\"id\":{},\"module\":{},\"segment\":{},\"offset\":{},\"address\":{},\"size\":{},\"nb_taken\":{},\"mode\":{},\"nb_instructions\":{}}}",
+                block_id,
+                block.module_id,
+                block.segment_id,
+                block.segment_offset,
+                address.map_or("null".to_string(), |address| address.to_string()),
+                block.size,
+                block.nb_taken,
+                mode.map_or("null".to_string(), |mode| json_string(&mode.to_string())),
+                block
+                    .nb_instructions
+                    .map_or("null".to_string(), |nb| nb.to_string())
+            )?,
+        }
+
+        Ok(())
+    }
+
+    fn edge(&mut self, src: u64, dst: u64, nb_taken: u64) -> Result<(), Error> {
+        match self.format {
+            TextFormat::Csv => writeln!(self.edges, "{src},{dst},{nb_taken}")?,
+            TextFormat::Jsonl => writeln!(
+                self.edges,
+                "{{\"src\":{src},\"dst\":{dst},\"nb_taken\":{nb_taken}}}"
+            )?,
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Error> {
+        self.modules.flush()?;
+        self.blocks.flush()?;
+        self.edges.flush()?;
+
+        Ok(())
+    }
+}
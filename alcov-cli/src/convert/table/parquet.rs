@@ -0,0 +1,256 @@
+use ::parquet::arrow::ArrowWriter;
+use arrow_array::builder::{StringBuilder, UInt16Builder, UInt32Builder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovBlock, AlcovIsaMode, AlcovModule, Error};
+
+use crate::convert::table::{TableWriter, segments_str};
+
+/// Rows buffered before being handed to the parquet writer.
+const BATCH_SIZE: usize = 1 << 16;
+
+fn parquet_error<E>(err: E) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Error::Io(io::Error::other(err))
+}
+
+/// A parquet file written by batches of rows.
+struct Table<B> {
+    writer: ArrowWriter<File>,
+    schema: SchemaRef,
+    builders: B,
+    nb_rows: usize,
+}
+
+trait Builders {
+    fn finish(&mut self) -> Vec<ArrayRef>;
+}
+
+impl<B> Table<B>
+where
+    B: Builders,
+{
+    fn create(path: &Path, fields: Vec<Field>, builders: B) -> Result<Self, Error> {
+        let schema = Arc::new(Schema::new(fields));
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), None)
+            .map_err(parquet_error)?;
+
+        Ok(Self {
+            writer,
+            schema,
+            builders,
+            nb_rows: 0,
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.nb_rows == 0 {
+            return Ok(());
+        }
+
+        let batch = RecordBatch::try_new(self.schema.clone(), self.builders.finish())
+            .map_err(parquet_error::<ArrowError>)?;
+        self.writer.write(&batch).map_err(parquet_error)?;
+        self.nb_rows = 0;
+
+        Ok(())
+    }
+
+    /// Account for a new row, flushing the batch if it is full.
+    fn row_added(&mut self) -> Result<(), Error> {
+        self.nb_rows += 1;
+
+        if self.nb_rows == BATCH_SIZE {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn close(mut self) -> Result<(), Error> {
+        self.flush()?;
+        self.writer.close().map_err(parquet_error)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct ModuleBuilders {
+    id: UInt16Builder,
+    path: StringBuilder,
+    base: UInt64Builder,
+    segments: StringBuilder,
+}
+
+impl Builders for ModuleBuilders {
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.id.finish()),
+            Arc::new(self.path.finish()),
+            Arc::new(self.base.finish()),
+            Arc::new(self.segments.finish()),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct BlockBuilders {
+    id: UInt64Builder,
+    module: UInt16Builder,
+    segment: UInt16Builder,
+    offset: UInt64Builder,
+    address: UInt64Builder,
+    size: UInt32Builder,
+    nb_taken: UInt64Builder,
+    mode: StringBuilder,
+    nb_instructions: UInt32Builder,
+}
+
+impl Builders for BlockBuilders {
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.id.finish()),
+            Arc::new(self.module.finish()),
+            Arc::new(self.segment.finish()),
+            Arc::new(self.offset.finish()),
+            Arc::new(self.address.finish()),
+            Arc::new(self.size.finish()),
+            Arc::new(self.nb_taken.finish()),
+            Arc::new(self.mode.finish()),
+            Arc::new(self.nb_instructions.finish()),
+        ]
+    }
+}
+
+#[derive(Default)]
+struct EdgeBuilders {
+    src: UInt64Builder,
+    dst: UInt64Builder,
+    nb_taken: UInt64Builder,
+}
+
+impl Builders for EdgeBuilders {
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.src.finish()),
+            Arc::new(self.dst.finish()),
+            Arc::new(self.nb_taken.finish()),
+        ]
+    }
+}
+
+/// Apache Parquet tables, in `modules`, `blocks` and `edges` files of a directory.
+pub struct ParquetTables {
+    modules: Table<ModuleBuilders>,
+    blocks: Table<BlockBuilders>,
+    edges: Table<EdgeBuilders>,
+}
+
+impl ParquetTables {
+    pub fn create(dir: &Path) -> Result<Self, Error> {
+        fs::create_dir_all(dir)?;
+
+        Ok(Self {
+            modules: Table::create(
+                &dir.join("modules.parquet"),
+                vec![
+                    Field::new("id", DataType::UInt16, false),
+                    Field::new("path", DataType::Utf8, true),
+                    Field::new("base", DataType::UInt64, false),
+                    Field::new("segments", DataType::Utf8, false),
+                ],
+                ModuleBuilders::default(),
+            )?,
+            blocks: Table::create(
+                &dir.join("blocks.parquet"),
+                vec![
+                    Field::new("id", DataType::UInt64, false),
+                    Field::new("module", DataType::UInt16, false),
+                    Field::new("segment", DataType::UInt16, false),
+                    Field::new("offset", DataType::UInt64, false),
+                    Field::new("address", DataType::UInt64, true),
+                    Field::new("size", DataType::UInt32, false),
+                    Field::new("nb_taken", DataType::UInt64, false),
+                    Field::new("mode", DataType::Utf8, true),
+                    Field::new("nb_instructions", DataType::UInt32, true),
+                ],
+                BlockBuilders::default(),
+            )?,
+            edges: Table::create(
+                &dir.join("edges.parquet"),
+                vec![
+                    Field::new("src", DataType::UInt64, false),
+                    Field::new("dst", DataType::UInt64, false),
+                    Field::new("nb_taken", DataType::UInt64, false),
+                ],
+                EdgeBuilders::default(),
+            )?,
+        })
+    }
+}
+
+impl TableWriter for ParquetTables {
+    fn module(&mut self, module_id: u16, module: &AlcovModule) -> Result<(), Error> {
+        let builders = &mut self.modules.builders;
+        builders.id.append_value(module_id);
+        builders.path.append_option(
+            module
+                .path
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned()),
+        );
+        builders.base.append_value(module.base_address);
+        builders.segments.append_value(segments_str(module));
+
+        self.modules.row_added()
+    }
+
+    fn block(
+        &mut self,
+        block_id: u64,
+        block: &AlcovBlock,
+        address: Option<u64>,
+        mode: Option<AlcovIsaMode>,
+    ) -> Result<(), Error> {
+        let builders = &mut self.blocks.builders;
+        builders.id.append_value(block_id);
+        builders.module.append_value(block.module_id);
+        builders.segment.append_value(block.segment_id);
+        builders.offset.append_value(block.segment_offset);
+        builders.address.append_option(address);
+        builders.size.append_value(block.size);
+        builders.nb_taken.append_value(block.nb_taken);
+        builders
+            .mode
+            .append_option(mode.map(|mode| mode.to_string()));
+        builders
+            .nb_instructions
+            .append_option(block.nb_instructions);
+
+        self.blocks.row_added()
+    }
+
+    fn edge(&mut self, src: u64, dst: u64, nb_taken: u64) -> Result<(), Error> {
+        let builders = &mut self.edges.builders;
+        builders.src.append_value(src);
+        builders.dst.append_value(dst);
+        builders.nb_taken.append_value(nb_taken);
+
+        self.edges.row_added()
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Error> {
+        self.modules.close()?;
+        self.blocks.close()?;
+        self.edges.close()
+    }
+}
@@ -0,0 +1,94 @@
+use clap::{Args, Subcommand};
+use rusqlite::Connection;
+use std::io;
+use std::path::Path;
+
+#[cfg(feature = "v0")]
+use alcov::v0::Error;
+
+use crate::db::import::DbImport;
+use crate::db::query::DbQuery;
+
+pub mod import;
+pub mod query;
+
+/// Tables of a coverage database.
+///
+/// Modules are identified by their path and GNU build ID (empty if unknown), and blocks by
+/// their module and offset from the module base address. Each imported trace records
+/// where its modules were loaded, and its block and edge hits.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS traces (
+    id INTEGER PRIMARY KEY,
+    file TEXT NOT NULL UNIQUE,
+    input_path TEXT
+);
+CREATE TABLE IF NOT EXISTS modules (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL,
+    build_id TEXT NOT NULL,
+    UNIQUE (path, build_id)
+);
+CREATE TABLE IF NOT EXISTS blocks (
+    id INTEGER PRIMARY KEY,
+    module_id INTEGER NOT NULL REFERENCES modules (id),
+    offset INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    UNIQUE (module_id, offset)
+);
+CREATE TABLE IF NOT EXISTS trace_modules (
+    trace_id INTEGER NOT NULL REFERENCES traces (id),
+    module_id INTEGER NOT NULL REFERENCES modules (id),
+    base_address INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    PRIMARY KEY (trace_id, module_id)
+) WITHOUT ROWID;
+CREATE TABLE IF NOT EXISTS trace_blocks (
+    trace_id INTEGER NOT NULL REFERENCES traces (id),
+    block_id INTEGER NOT NULL REFERENCES blocks (id),
+    nb_taken INTEGER NOT NULL,
+    PRIMARY KEY (trace_id, block_id)
+) WITHOUT ROWID;
+CREATE INDEX IF NOT EXISTS trace_blocks_block ON trace_blocks (block_id);
+CREATE TABLE IF NOT EXISTS trace_edges (
+    trace_id INTEGER NOT NULL REFERENCES traces (id),
+    src_block_id INTEGER NOT NULL REFERENCES blocks (id),
+    dst_block_id INTEGER NOT NULL REFERENCES blocks (id),
+    nb_taken INTEGER NOT NULL,
+    PRIMARY KEY (trace_id, src_block_id, dst_block_id)
+) WITHOUT ROWID;
+";
+
+pub fn sql_error(err: rusqlite::Error) -> Error {
+    Error::Io(io::Error::other(err))
+}
+
+/// Open a coverage database, creating its tables if needed.
+pub fn open(path: &Path) -> Result<Connection, Error> {
+    let conn = Connection::open(path).map_err(sql_error)?;
+    conn.execute_batch(SCHEMA).map_err(sql_error)?;
+
+    Ok(conn)
+}
+
+/// Store many traces in a SQLite database, and query it
+#[derive(Clone, Debug, Args)]
+pub struct Db {
+    #[command(subcommand)]
+    pub command: DbCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum DbCommand {
+    Import(DbImport),
+    Query(DbQuery),
+}
+
+impl Db {
+    pub fn run(self) -> Result<(), Error> {
+        match self.command {
+            DbCommand::Import(import) => import.run(),
+            DbCommand::Query(query) => query.run(),
+        }
+    }
+}
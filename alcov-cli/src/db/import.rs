@@ -0,0 +1,272 @@
+use clap::Args;
+use object::Object;
+use rusqlite::{OptionalExtension, Transaction, params};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, AlcovModule, AlcovModuleIdentity, Error};
+
+use crate::db::{open, sql_error};
+
+/// Import alcov files in a coverage database
+#[derive(Clone, Debug, Args)]
+pub struct DbImport {
+    /// Directory prepended to module paths to find the files, for build IDs
+    #[arg(short, long)]
+    pub sysroot: Option<PathBuf>,
+    /// alcov files, or directories searched recursively for `.alcov` files
+    #[arg(required = true)]
+    pub traces: Vec<PathBuf>,
+    /// Database, created if needed
+    pub db: PathBuf,
+}
+
+fn find_traces(path: &Path, traces: &mut Vec<PathBuf>) -> Result<(), Error> {
+    if !path.is_dir() {
+        traces.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            find_traces(&entry, traces)?;
+        } else if entry
+            .extension()
+            .is_some_and(|extension| extension == "alcov")
+        {
+            traces.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+impl DbImport {
+    /// GNU build ID of a module file, as hex. Empty if the file or the build ID is missing.
+    fn build_id(&self, path: &Path) -> String {
+        let file_path = match &self.sysroot {
+            Some(sysroot) => sysroot.join(path.strip_prefix("/").unwrap_or(path)),
+            None => path.to_path_buf(),
+        };
+
+        let Ok(data) = fs::read(file_path) else {
+            return String::new();
+        };
+
+        match object::File::parse(&*data).map(|file| file.build_id()) {
+            Ok(Ok(Some(build_id))) => build_id.iter().map(|b| format!("{b:02x}")).collect(),
+            _ => String::new(),
+        }
+    }
+
+    fn module_id(
+        &self,
+        tx: &Transaction,
+        module: &AlcovModule,
+        path: &Path,
+        build_ids: &mut HashMap<PathBuf, String>,
+    ) -> Result<i64, Error> {
+        // the build ID recorded in the trace is preferred to the one of the file on disk.
+        let recorded = module
+            .identities
+            .iter()
+            .find_map(|identity| match identity {
+                AlcovModuleIdentity::GnuBuildId(build_id) => Some(
+                    build_id
+                        .iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect::<String>(),
+                ),
+                _ => None,
+            });
+        let build_id: &str = match &recorded {
+            Some(build_id) => build_id,
+            None => build_ids
+                .entry(path.to_path_buf())
+                .or_insert_with(|| self.build_id(path)),
+        };
+        let path = path.to_string_lossy();
+
+        tx.execute(
+            "INSERT OR IGNORE INTO modules (path, build_id) VALUES (?1, ?2)",
+            params![path, build_id],
+        )
+        .map_err(sql_error)?;
+
+        tx.query_row(
+            "SELECT id FROM modules WHERE path = ?1 AND build_id = ?2",
+            params![path, build_id],
+            |row| row.get(0),
+        )
+        .map_err(sql_error)
+    }
+
+    fn import(
+        &self,
+        tx: &Transaction,
+        trace: &Path,
+        alcov: &Alcov,
+        build_ids: &mut HashMap<PathBuf, String>,
+    ) -> Result<(), Error> {
+        tx.execute(
+            "INSERT INTO traces (file, input_path) VALUES (?1, ?2)",
+            params![
+                trace.to_string_lossy(),
+                alcov
+                    .hdr
+                    .input_path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy())
+            ],
+        )
+        .map_err(sql_error)?;
+        let trace_id = tx.last_insert_rowid();
+
+        // modules without path cannot be identified across traces.
+        let mut module_ids: Vec<Option<i64>> = Vec::new();
+        for module in &alcov.modules {
+            let Some(path) = &module.path else {
+                eprintln!("{}: module without path, skipping.", trace.display());
+                module_ids.push(None);
+                continue;
+            };
+
+            let module_id = self.module_id(tx, module, path, build_ids)?;
+            let size = module
+                .segments
+                .iter()
+                .map(|segment| segment.module_range.end)
+                .max()
+                .unwrap_or(0);
+
+            tx.execute(
+                "INSERT OR IGNORE INTO trace_modules (trace_id, module_id, base_address, size)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![trace_id, module_id, module.base_address as i64, size as i64],
+            )
+            .map_err(sql_error)?;
+            module_ids.push(Some(module_id));
+        }
+
+        let mut insert_block = tx
+            .prepare(
+                "INSERT INTO blocks (module_id, offset, size) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (module_id, offset) DO UPDATE SET size = max(size, excluded.size)
+                 RETURNING id",
+            )
+            .map_err(sql_error)?;
+        let mut insert_hits = tx
+            .prepare(
+                "INSERT INTO trace_blocks (trace_id, block_id, nb_taken) VALUES (?1, ?2, ?3)
+                 ON CONFLICT DO UPDATE SET nb_taken = nb_taken + excluded.nb_taken",
+            )
+            .map_err(sql_error)?;
+
+        let mut block_ids: Vec<Option<i64>> = Vec::with_capacity(alcov.blocks.len());
+        for block in &alcov.blocks {
+            let module_id = module_ids.get(block.module_id as usize).copied().flatten();
+            let offset = alcov
+                .modules
+                .get(block.module_id as usize)
+                .and_then(|module| module.module_offset(block.segment_id, block.segment_offset));
+
+            let (Some(module_id), Some(offset)) = (module_id, offset) else {
+                block_ids.push(None);
+                continue;
+            };
+
+            let block_id: i64 = insert_block
+                .query_row(params![module_id, offset as i64, block.size], |row| {
+                    row.get(0)
+                })
+                .map_err(sql_error)?;
+            insert_hits
+                .execute(params![trace_id, block_id, block.nb_taken as i64])
+                .map_err(sql_error)?;
+
+            block_ids.push(Some(block_id));
+        }
+
+        if let Some(edges) = &alcov.edges {
+            let mut insert_edge = tx
+                .prepare(
+                    "INSERT INTO trace_edges (trace_id, src_block_id, dst_block_id, nb_taken)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT DO UPDATE SET nb_taken = nb_taken + excluded.nb_taken",
+                )
+                .map_err(sql_error)?;
+
+            for (src, block_edges) in edges.adj_list.iter().enumerate() {
+                let Some(src_id) = block_ids.get(src).copied().flatten() else {
+                    continue;
+                };
+
+                for (dst, metadata) in &block_edges.dst_modules {
+                    let Some(dst_id) = block_ids.get(dst.dst_block_id as usize).copied().flatten()
+                    else {
+                        continue;
+                    };
+
+                    insert_edge
+                        .execute(params![trace_id, src_id, dst_id, metadata.nb_taken as i64])
+                        .map_err(sql_error)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run(self) -> Result<(), Error> {
+        let mut conn = open(&self.db)?;
+
+        let mut traces: Vec<PathBuf> = Vec::new();
+        for path in &self.traces {
+            find_traces(path, &mut traces)?;
+        }
+
+        let mut build_ids: HashMap<PathBuf, String> = HashMap::new();
+
+        for trace in &traces {
+            let tx = conn.transaction().map_err(sql_error)?;
+
+            let already_imported = tx
+                .query_row(
+                    "SELECT id FROM traces WHERE file = ?1",
+                    params![trace.to_string_lossy()],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()
+                .map_err(sql_error)?
+                .is_some();
+            if already_imported {
+                eprintln!("{}: already imported, skipping.", trace.display());
+                continue;
+            }
+
+            let alcov = match File::open(trace)
+                .map_err(Error::from)
+                .and_then(|file| Alcov::read(&mut BufReader::new(file)))
+            {
+                Ok(alcov) => alcov,
+                Err(err) => {
+                    eprintln!("{}: could not read ({:?}), skipping.", trace.display(), err);
+                    continue;
+                }
+            };
+
+            self.import(&tx, trace, &alcov, &mut build_ids)?;
+            tx.commit().map_err(sql_error)?;
+        }
+
+        Ok(())
+    }
+}
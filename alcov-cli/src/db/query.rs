@@ -0,0 +1,174 @@
+use clap::{Args, Subcommand};
+use rusqlite::{Connection, params};
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::Error;
+
+use crate::db::{open, sql_error};
+
+/// Query a coverage database
+#[derive(Clone, Debug, Args)]
+pub struct DbQuery {
+    /// Database
+    pub db: PathBuf,
+    #[command(subcommand)]
+    pub query: Query,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Query {
+    /// Inputs covering an address, with the number of times its block was taken
+    Covering {
+        /// Address, in hexadecimal. Offset from the module base address with `--module`
+        #[arg(value_parser = parse_hex)]
+        address: u64,
+        /// Path or file name of the module
+        #[arg(short, long)]
+        module: Option<String>,
+    },
+    /// Blocks covered only by an input, or a trace file
+    Only {
+        /// Input path, or alcov file as imported
+        input: String,
+    },
+}
+
+fn parse_hex(hex: &str) -> Result<u64, String> {
+    u64::from_str_radix(hex.strip_prefix("0x").unwrap_or(hex), 16).map_err(|err| err.to_string())
+}
+
+/// Inputs covering an absolute address, from where the modules were loaded in each trace.
+fn covering_address(conn: &Connection, address: u64) -> Result<Vec<(String, u64)>, Error> {
+    let mut trace_modules = conn
+        .prepare(
+            "SELECT t.id, coalesce(t.input_path, t.file), tm.module_id, tm.base_address, tm.size
+             FROM trace_modules tm JOIN traces t ON t.id = tm.trace_id",
+        )
+        .map_err(sql_error)?;
+    let mut hits = conn
+        .prepare(
+            "SELECT tb.nb_taken FROM trace_blocks tb JOIN blocks b ON b.id = tb.block_id
+             WHERE tb.trace_id = ?1 AND b.module_id = ?2 AND b.offset <= ?3
+             AND ?3 < b.offset + b.size",
+        )
+        .map_err(sql_error)?;
+
+    let rows = trace_modules
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)? as u64,
+                row.get::<_, i64>(4)? as u64,
+            ))
+        })
+        .map_err(sql_error)?;
+
+    let mut inputs: Vec<(String, u64)> = Vec::new();
+    for row in rows {
+        let (trace_id, input, module_id, base_address, size) = row.map_err(sql_error)?;
+
+        let offset = address.wrapping_sub(base_address);
+        if offset >= size {
+            continue;
+        }
+
+        let nb_taken: u64 = hits
+            .query_map(params![trace_id, module_id, offset as i64], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map_err(sql_error)?
+            .map(|nb_taken| nb_taken.map(|nb_taken| nb_taken as u64))
+            .sum::<Result<u64, _>>()
+            .map_err(sql_error)?;
+        if nb_taken != 0 {
+            inputs.push((input, nb_taken));
+        }
+    }
+
+    Ok(inputs)
+}
+
+/// Inputs covering an offset in the modules matching a path or a file name.
+fn covering_offset(
+    conn: &Connection,
+    module: &str,
+    offset: u64,
+) -> Result<Vec<(String, u64)>, Error> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT coalesce(t.input_path, t.file), sum(tb.nb_taken)
+             FROM trace_blocks tb
+             JOIN traces t ON t.id = tb.trace_id
+             JOIN blocks b ON b.id = tb.block_id
+             JOIN modules m ON m.id = b.module_id
+             WHERE (m.path = ?1 OR m.path LIKE '%/' || ?1)
+             AND b.offset <= ?2 AND ?2 < b.offset + b.size
+             GROUP BY t.id ORDER BY t.id",
+        )
+        .map_err(sql_error)?;
+
+    stmt.query_map(params![module, offset as i64], |row| {
+        Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+    })
+    .map_err(sql_error)?
+    .collect::<Result<_, _>>()
+    .map_err(sql_error)
+}
+
+impl DbQuery {
+    pub fn run(self) -> Result<(), Error> {
+        let conn = open(&self.db)?;
+
+        match self.query {
+            Query::Covering { address, module } => {
+                let inputs = match module {
+                    Some(module) => covering_offset(&conn, &module, address)?,
+                    None => covering_address(&conn, address)?,
+                };
+
+                for (input, nb_taken) in inputs {
+                    println!("{input} {nb_taken}");
+                }
+            }
+            Query::Only { input } => {
+                let mut stmt = conn
+                    .prepare(
+                        "WITH selected AS (SELECT id FROM traces WHERE input_path = ?1 OR file = ?1)
+                         SELECT m.path, b.offset, b.size, sum(tb.nb_taken)
+                         FROM trace_blocks tb
+                         JOIN blocks b ON b.id = tb.block_id
+                         JOIN modules m ON m.id = b.module_id
+                         WHERE tb.trace_id IN selected
+                         AND NOT EXISTS (
+                             SELECT 1 FROM trace_blocks other
+                             WHERE other.block_id = tb.block_id
+                             AND other.trace_id NOT IN selected
+                         )
+                         GROUP BY b.id ORDER BY m.path, b.offset",
+                    )
+                    .map_err(sql_error)?;
+
+                let rows = stmt
+                    .query_map(params![input], |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, i64>(1)? as u64,
+                            row.get::<_, u32>(2)?,
+                            row.get::<_, i64>(3)? as u64,
+                        ))
+                    })
+                    .map_err(sql_error)?;
+
+                for row in rows {
+                    let (path, offset, size, nb_taken) = row.map_err(sql_error)?;
+                    println!("{path}+{offset:#x} {size} {nb_taken}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use std::io;
 use std::io::Write;
 
 #[cfg(feature = "v0")]
-use alcov::v0::{Alcov, Error};
+use alcov::v0::{Alcov, AlcovPerms, Error};
 use clap_stdin::FileOrStdin;
 
 /// Read an alcov file
@@ -18,6 +18,9 @@ pub struct Dump {
     /// Show edges
     #[arg(short, long)]
     pub edges: bool,
+    /// Show the coverage of each thread
+    #[arg(short = 't', long)]
+    pub by_thread: bool,
     /// Input, or empty to get from STDIN.
     #[arg(default_value = "-")]
     input: FileOrStdin,
@@ -34,6 +37,10 @@ impl Dump {
             Self::write_md(&mut stdout, &alcov)?;
         }
 
+        if self.by_thread {
+            Self::write_threads(&mut stdout, &alcov)?;
+        }
+
         Ok(())
     }
 
@@ -50,11 +57,39 @@ impl Dump {
 
         let flags = alcov.get_flags();
         writeln!(writer, "Flags: {:}", flags)?;
+        writeln!(writer, "Blocks codec: {}", alcov.hdr.blocks_codec)?;
+        if alcov.has_edges() {
+            writeln!(writer, "Edges codec: {}", alcov.hdr.edges_codec)?;
+        }
+        if let Some(checksum) = alcov.hdr.checksum {
+            writeln!(writer, "Checksum: {}", checksum)?;
+        }
         
         if let Some(input_path) = &alcov.hdr.input_path {
             writeln!(writer, "Input path: {}", input_path.display())?;
         }
 
+        if let Some(input) = &alcov.hdr.input {
+            write!(
+                writer,
+                "Input: {} bytes ({}), SHA-256 ",
+                input.data.len(),
+                input.codec
+            )?;
+            input
+                .sha256()
+                .iter()
+                .try_for_each(|b| write!(writer, "{b:02x}"))?;
+            writeln!(writer)?;
+        }
+
+        if !alcov.hdr.metadata.is_empty() {
+            writeln!(writer, "# {} Metadata entries", alcov.hdr.metadata.len())?;
+            for (key, value) in &alcov.hdr.metadata {
+                writeln!(writer, "\t{}: {}", key, value)?;
+            }
+        }
+
         writeln!(writer, "# {} Blocks", alcov.blocks.len())?;
 
         if let Some(edges) = &alcov.edges {
@@ -72,6 +107,18 @@ impl Dump {
                 writeln!(writer, "\t<no path>")?;
             }
 
+            for identity in &module.identities {
+                writeln!(writer, "\tIdentity: {}", identity)?;
+            }
+
+            if let Some(arch) = &module.arch {
+                writeln!(writer, "\tArchitecture: {}", arch)?;
+            }
+
+            if let Some(code) = &module.code {
+                writeln!(writer, "\tCode: {} bytes", code.len())?;
+            }
+
             writeln!(writer, "\t# {} Segments", module.segments.len())?;
             for segment in &module.segments {
                 writeln!(
@@ -79,10 +126,76 @@ impl Dump {
                     "\t\t Range {:#x} -> {:#x} from module base.",
                     segment.module_range.start, segment.module_range.end
                 )?;
+                if let Some(file_offset) = segment.file_offset {
+                    writeln!(writer, "\t\t\t File offset: {:#x}", file_offset)?;
+                }
+                if !segment.perms.is_empty() {
+                    writeln!(writer, "\t\t\t Permissions: {}", perms(segment.perms))?;
+                }
             }
             writeln!(writer)?;
         }
 
         Ok(())
     }
+
+    pub fn write_threads<W>(writer: &mut W, alcov: &Alcov) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        writeln!(writer, "# {} Threads", alcov.threads.len())?;
+
+        for thread in &alcov.threads {
+            write!(writer, "Thread {}", thread.tid)?;
+            if let Some(name) = &thread.name {
+                write!(writer, " ({})", name)?;
+            }
+            writeln!(
+                writer,
+                ": {}/{} blocks ({:.1}%), {} hits, {} edges",
+                thread.blocks.len(),
+                alcov.blocks.len(),
+                100.0 * thread.blocks.len() as f64 / alcov.blocks.len().max(1) as f64,
+                thread.nb_taken(),
+                thread.edges.len()
+            )?;
+
+            let mut module_blocks = vec![0u64; alcov.modules.len()];
+            for block_id in thread.blocks.keys() {
+                if let Some(nb_blocks) = alcov
+                    .blocks
+                    .get(*block_id as usize)
+                    .and_then(|block| module_blocks.get_mut(block.module_id as usize))
+                {
+                    *nb_blocks += 1;
+                }
+            }
+
+            for (module, nb_blocks) in alcov.modules.iter().zip(module_blocks) {
+                if nb_blocks == 0 {
+                    continue;
+                }
+
+                match &module.path {
+                    Some(path) => write!(writer, "\t{}", path.display())?,
+                    None => write!(writer, "\t<{:#x}>", module.base_address)?,
+                }
+                writeln!(writer, ": {} blocks", nb_blocks)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Permissions in the `rwx` notation of `/proc/<pid>/maps`.
+fn perms(perms: AlcovPerms) -> String {
+    [
+        (AlcovPerms::Read, 'r'),
+        (AlcovPerms::Write, 'w'),
+        (AlcovPerms::Execute, 'x'),
+    ]
+    .into_iter()
+    .map(|(perm, c)| if perms.contains(perm) { c } else { '-' })
+    .collect()
 }
@@ -0,0 +1,40 @@
+use clap::Args;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovReader, Error};
+use clap_stdin::FileOrStdin;
+
+/// Write the input embedded in an alcov file, to reproduce the traced run
+#[derive(Clone, Debug, Args)]
+pub struct ExtractInput {
+    /// Output file, or STDOUT if not given
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+impl ExtractInput {
+    pub fn run(self) -> Result<(), Error> {
+        let input_rdr = self.input.into_reader().unwrap();
+        let alcov_rdr = AlcovReader::new(input_rdr)?;
+
+        let Some(input) = alcov_rdr.hdr.input else {
+            eprintln!("no input is embedded in the trace.");
+            process::exit(1);
+        };
+
+        match &self.output {
+            Some(output) => fs::write(output, &input.data)?,
+            None => io::stdout().write_all(&input.data)?,
+        }
+
+        Ok(())
+    }
+}
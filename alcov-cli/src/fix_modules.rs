@@ -0,0 +1,79 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, AlcovModule, Error};
+use clap_stdin::FileOrStdin;
+
+/// Fill in or correct the segments of modules, using the ELF files on disk
+#[derive(Clone, Debug, Args)]
+pub struct FixModules {
+    /// Directory prepended to module paths to find the files (e.g. a copy of the target's root filesystem)
+    #[arg(short, long)]
+    pub sysroot: Option<PathBuf>,
+    /// Output alcov file
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+impl FixModules {
+    pub fn run(self) -> Result<(), Error> {
+        let mut input_rdr = self.input.into_reader().unwrap();
+        let mut alcov = Alcov::read(&mut input_rdr)?;
+
+        for module_id in 0..alcov.modules.len() {
+            let module = &alcov.modules[module_id];
+            let Some(path) = module.path.clone() else {
+                continue;
+            };
+
+            let file_path = match &self.sysroot {
+                Some(sysroot) => sysroot.join(path.strip_prefix("/").unwrap_or(&path)),
+                None => path.clone(),
+            };
+
+            if !file_path.is_file() {
+                eprintln!("{}: file not found, skipping.", file_path.display());
+                continue;
+            }
+
+            let mut elf_module = match AlcovModule::from_elf(&file_path, module.base_address) {
+                Ok(elf_module) => elf_module,
+                Err(err) => {
+                    eprintln!(
+                        "{}: not a valid ELF ({:?}), skipping.",
+                        file_path.display(),
+                        err
+                    );
+                    continue;
+                }
+            };
+            if module.same_file(&elf_module) == Some(false) {
+                eprintln!(
+                    "{}: not the file of the trace (different identity), skipping.",
+                    file_path.display()
+                );
+                continue;
+            }
+            elf_module.path = Some(path);
+
+            if let Err(err) = alcov.replace_module(u16::try_from(module_id)?, elf_module) {
+                eprintln!(
+                    "{}: blocks do not match the ELF segments ({:?}), skipping.",
+                    file_path.display(),
+                    err
+                );
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        alcov.write(&mut writer)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,376 @@
+use clap::Args;
+use object::{Object, ObjectSymbol, SymbolKind};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, AlcovModule, Error};
+use clap_stdin::FileOrStdin;
+
+/// Export the executed control-flow graph in the Graphviz DOT format
+#[derive(Clone, Debug, Args)]
+pub struct Graph {
+    /// Output file, or empty to write to STDOUT.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[command(flatten)]
+    pub filter: GraphFilter,
+    /// Group blocks by module
+    #[arg(short, long)]
+    pub cluster: bool,
+    /// Directory prepended to module paths to find the files, for symbols
+    #[arg(short, long)]
+    pub sysroot: Option<PathBuf>,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+/// Selection of the blocks to export. Filters can be combined.
+#[derive(Clone, Debug, Args)]
+pub struct GraphFilter {
+    /// Only keep the blocks of a function, by symbol name
+    #[arg(short, long)]
+    pub function: Option<String>,
+    /// Only keep the blocks in an address range, like `0x401000-0x402000`
+    #[arg(short, long, value_parser = parse_range)]
+    pub range: Option<Range<u64>>,
+    /// Only keep the blocks around the block containing this address
+    #[arg(short, long, value_parser = parse_hex)]
+    pub around: Option<u64>,
+    /// Number of edges to follow from the `--around` block
+    #[arg(long, default_value_t = 2, requires = "around")]
+    pub hops: usize,
+}
+
+fn parse_hex(hex: &str) -> Result<u64, String> {
+    u64::from_str_radix(hex.strip_prefix("0x").unwrap_or(hex), 16).map_err(|err| err.to_string())
+}
+
+fn parse_range(range: &str) -> Result<Range<u64>, String> {
+    let (start, end) = range
+        .split_once('-')
+        .ok_or("expected <start>-<end>".to_string())?;
+
+    Ok(parse_hex(start)?..parse_hex(end)?)
+}
+
+/// Function symbols of a module, at runtime addresses.
+struct Symbols {
+    /// sorted (range, name).
+    functions: Vec<(Range<u64>, String)>,
+}
+
+impl Symbols {
+    /// Load the symbols of a module. Modules without path or readable ELF file have none.
+    fn load(module: &AlcovModule, sysroot: Option<&PathBuf>) -> Self {
+        let mut functions: Vec<(Range<u64>, String)> = Vec::new();
+
+        let file_path = module.path.as_ref().map(|path| match sysroot {
+            Some(sysroot) => sysroot.join(path.strip_prefix("/").unwrap_or(path)),
+            None => path.clone(),
+        });
+
+        if let Some(file_path) = file_path
+            && let Ok(data) = fs::read(&file_path)
+            && let Ok(file) = object::File::parse(&*data)
+            && let Ok(image_base) = AlcovModule::elf_image_base(&file)
+        {
+            let bias = module.base_address.wrapping_sub(image_base);
+
+            functions = file
+                .symbols()
+                .chain(file.dynamic_symbols())
+                .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.size() > 0)
+                .filter_map(|symbol| {
+                    let start = symbol.address().wrapping_add(bias);
+                    Some((
+                        start..(start + symbol.size()),
+                        symbol.name().ok()?.to_string(),
+                    ))
+                })
+                .collect();
+            functions.sort_by_key(|(range, _)| range.start);
+            functions.dedup_by_key(|(range, _)| range.start);
+        }
+
+        Self { functions }
+    }
+
+    fn function_of(&self, address: u64) -> Option<&(Range<u64>, String)> {
+        let idx = self
+            .functions
+            .partition_point(|(range, _)| range.start <= address);
+
+        self.functions[..idx]
+            .last()
+            .filter(|(range, _)| range.contains(&address))
+    }
+}
+
+impl GraphFilter {
+    /// Remove the nodes not matching the filters.
+    fn apply(&self, alcov: &Alcov, symbols: &[Symbols], nodes: &mut [Option<Node>]) {
+        if let Some(function) = &self.function {
+            let ranges: Vec<&Range<u64>> = symbols
+                .iter()
+                .flat_map(|symbols| &symbols.functions)
+                .filter(|(_, name)| name == function)
+                .map(|(range, _)| range)
+                .collect();
+
+            for node in nodes.iter_mut() {
+                if node
+                    .as_ref()
+                    .is_some_and(|node| !ranges.iter().any(|range| range.contains(&node.address)))
+                {
+                    *node = None;
+                }
+            }
+        }
+
+        if let Some(range) = &self.range {
+            for node in nodes.iter_mut() {
+                if node
+                    .as_ref()
+                    .is_some_and(|node| !range.contains(&node.address))
+                {
+                    *node = None;
+                }
+            }
+        }
+
+        if let Some(around) = self.around {
+            let neighbourhood = self.neighbourhood(alcov, around);
+
+            for (block_id, node) in nodes.iter_mut().enumerate() {
+                if !neighbourhood.contains(&block_id) {
+                    *node = None;
+                }
+            }
+        }
+    }
+
+    /// Blocks at most `hops` edges away from the block containing `address`, in any
+    /// direction.
+    fn neighbourhood(&self, alcov: &Alcov, address: u64) -> BTreeSet<usize> {
+        let mut neighbourhood: BTreeSet<usize> = BTreeSet::new();
+
+        let Some(start) = alcov.blocks.iter().position(|block| {
+            let module = &alcov.modules[block.module_id as usize];
+            module
+                .module_offset(block.segment_id, block.segment_offset)
+                .is_some_and(|offset| {
+                    let start = module.base_address + offset;
+                    (start..(start + block.size.max(1) as u64)).contains(&address)
+                })
+        }) else {
+            return neighbourhood;
+        };
+
+        let mut neighbours: HashMap<usize, Vec<usize>> = HashMap::new();
+        if let Some(edges) = &alcov.edges {
+            for (src, block_edges) in edges.adj_list.iter().enumerate() {
+                for dst in block_edges.dst_modules.keys() {
+                    let dst = dst.dst_block_id as usize;
+                    neighbours.entry(src).or_default().push(dst);
+                    neighbours.entry(dst).or_default().push(src);
+                }
+            }
+        }
+
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::from([(start, 0)]);
+        neighbourhood.insert(start);
+
+        while let Some((block_id, distance)) = queue.pop_front() {
+            if distance == self.hops {
+                continue;
+            }
+
+            for neighbour in neighbours.get(&block_id).into_iter().flatten() {
+                if neighbourhood.insert(*neighbour) {
+                    queue.push_back((*neighbour, distance + 1));
+                }
+            }
+        }
+
+        neighbourhood
+    }
+}
+
+/// A block with its absolute address and label.
+struct Node {
+    module_id: u16,
+    address: u64,
+    label: String,
+}
+
+impl Graph {
+    pub fn run(self) -> Result<(), Error> {
+        let mut input_rdr = self.input.into_reader().unwrap();
+        let alcov = Alcov::read(&mut input_rdr)?;
+
+        let symbols: Vec<Symbols> = alcov
+            .modules
+            .iter()
+            .map(|module| Symbols::load(module, self.sysroot.as_ref()))
+            .collect();
+
+        let mut nodes: Vec<Option<Node>> = Vec::with_capacity(alcov.blocks.len());
+        for (block_id, block) in alcov.blocks.iter().enumerate() {
+            let module =
+                alcov
+                    .modules
+                    .get(block.module_id as usize)
+                    .ok_or(Error::BlockOutOfModule {
+                        block_id: block_id as u64,
+                    })?;
+            let module_offset = module
+                .module_offset(block.segment_id, block.segment_offset)
+                .ok_or(Error::BlockOutOfModule {
+                    block_id: block_id as u64,
+                })?;
+            let address = module.base_address + module_offset;
+
+            let name = match symbols[block.module_id as usize].function_of(address) {
+                Some((range, name)) => format!("{}+{:#x}", name, address - range.start),
+                None => {
+                    let module_name = module
+                        .path
+                        .as_ref()
+                        .and_then(|path| path.file_name())
+                        .map_or(format!("module{}", block.module_id), |name| {
+                            name.to_string_lossy().into_owned()
+                        });
+                    format!("{module_name}+{module_offset:#x}")
+                }
+            };
+
+            nodes.push(Some(Node {
+                module_id: block.module_id,
+                address,
+                label: format!("{}\\n{} taken", name, block.nb_taken),
+            }));
+        }
+
+        self.filter.apply(&alcov, &symbols, &mut nodes);
+
+        let mut writer: BufWriter<Box<dyn Write>> = match &self.output {
+            Some(output) => BufWriter::new(Box::new(File::create(output)?)),
+            None => BufWriter::new(Box::new(io::stdout())),
+        };
+
+        write_dot(&mut writer, &alcov, &nodes, self.cluster)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Write the remaining nodes, and the edges between them.
+fn write_dot<W>(
+    writer: &mut W,
+    alcov: &Alcov,
+    nodes: &[Option<Node>],
+    cluster: bool,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    writeln!(writer, "digraph alcov {{")?;
+    writeln!(writer, "\tnode [shape=box, fontname=monospace];")?;
+
+    let mut by_module: Vec<Vec<(usize, &Node)>> = vec![Vec::new(); alcov.modules.len()];
+    for (block_id, node) in nodes.iter().enumerate() {
+        if let Some(node) = node {
+            by_module[node.module_id as usize].push((block_id, node));
+        }
+    }
+
+    for (module_id, module_nodes) in by_module.iter().enumerate() {
+        if module_nodes.is_empty() {
+            continue;
+        }
+
+        let indent = if cluster {
+            let module = &alcov.modules[module_id];
+            let name = module
+                .path
+                .as_ref()
+                .map_or(format!("module{module_id}"), |path| {
+                    path.display().to_string()
+                });
+
+            writeln!(writer, "\tsubgraph cluster_{module_id} {{")?;
+            writeln!(writer, "\t\tlabel=\"{}\";", name.replace('"', "\\\""))?;
+            "\t\t"
+        } else {
+            "\t"
+        };
+
+        for (block_id, node) in module_nodes {
+            writeln!(
+                writer,
+                "{}b{} [label=\"{}\", tooltip=\"{:#x}\"];",
+                indent,
+                block_id,
+                node.label.replace('"', "\\\""),
+                node.address
+            )?;
+        }
+
+        if cluster {
+            writeln!(writer, "\t}}")?;
+        }
+    }
+
+    if let Some(edges) = &alcov.edges {
+        let max_taken = edges
+            .adj_list
+            .iter()
+            .flat_map(|block_edges| block_edges.dst_modules.values())
+            .map(|metadata| metadata.nb_taken)
+            .max()
+            .unwrap_or(0);
+
+        for (src, block_edges) in edges.adj_list.iter().enumerate() {
+            if nodes.get(src).is_none_or(|node| node.is_none()) {
+                continue;
+            }
+
+            let mut dsts: Vec<_> = block_edges.dst_modules.iter().collect();
+            dsts.sort_by_key(|(dst, _)| dst.dst_block_id);
+
+            for (dst, metadata) in dsts {
+                let dst = dst.dst_block_id as usize;
+                if nodes.get(dst).is_none_or(|node| node.is_none()) {
+                    continue;
+                }
+
+                let width = if max_taken > 1 && metadata.nb_taken > 0 {
+                    1.0 + 4.0 * (metadata.nb_taken as f64).ln() / (max_taken as f64).ln()
+                } else {
+                    1.0
+                };
+
+                // dot needs an integer weight, and gets slow with large ones.
+                let weight = 1 + metadata.nb_taken.max(1).ilog2();
+
+                writeln!(
+                    writer,
+                    "\tb{} -> b{} [label=\"{}\", weight={}, penwidth={:.2}];",
+                    src, dst, metadata.nb_taken, weight, width
+                )?;
+            }
+        }
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use clap::{Args, Subcommand};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, Error};
+
+use crate::import::callgrind::ImportCallgrind;
+use crate::import::kcov::ImportKcov;
+use crate::import::modoff::ImportModOff;
+use crate::import::perf::ImportPerf;
+use crate::import::qemu::ImportQemu;
+use crate::import::sancov::ImportSancov;
+
+pub mod callgrind;
+pub mod kcov;
+pub mod modoff;
+pub mod perf;
+pub mod qemu;
+pub mod sancov;
+
+/// Import coverage from another format
+#[derive(Clone, Debug, Args)]
+pub struct Import {
+    #[command(subcommand)]
+    pub format: ImportFormat,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ImportFormat {
+    Sancov(ImportSancov),
+    Qemu(ImportQemu),
+    Perf(ImportPerf),
+    Kcov(ImportKcov),
+    Callgrind(ImportCallgrind),
+    Modoff(ImportModOff),
+}
+
+/// Output of an import
+#[derive(Clone, Debug, Args)]
+pub struct ImportOutput {
+    /// Output alcov file
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Compress the output file
+    #[arg(short, long)]
+    pub compress: bool,
+}
+
+impl ImportOutput {
+    pub fn write(&self, alcov: &Alcov) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        alcov.write(&mut writer)
+    }
+}
+
+impl Import {
+    pub fn run(self) -> Result<(), Error> {
+        match self.format {
+            ImportFormat::Sancov(sancov) => sancov.run(),
+            ImportFormat::Qemu(qemu) => qemu.run(),
+            ImportFormat::Perf(perf) => perf.run(),
+            ImportFormat::Kcov(kcov) => kcov.run(),
+            ImportFormat::Callgrind(callgrind) => callgrind.run(),
+            ImportFormat::Modoff(modoff) => modoff.run(),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::import::callgrind::CallgrindImporter;
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovHeader, Error, ProcMaps};
+
+use crate::import::ImportOutput;
+
+/// Import Valgrind callgrind profiles, recorded with `--dump-instr=yes --collect-jumps=yes`
+#[derive(Clone, Debug, Args)]
+pub struct ImportCallgrind {
+    #[command(flatten)]
+    pub output: ImportOutput,
+    /// Memory mappings, in the `/proc/<pid>/maps` format. By default, a module is built
+    /// for each object of the profiles
+    #[arg(short, long)]
+    pub maps: Option<PathBuf>,
+    /// callgrind.out files
+    #[arg(required = true)]
+    pub profiles: Vec<PathBuf>,
+}
+
+impl ImportCallgrind {
+    pub fn run(self) -> Result<(), Error> {
+        let maps = match &self.maps {
+            Some(maps) => Some(ProcMaps::read(&mut File::open(maps)?)?),
+            None => None,
+        };
+
+        let mut importer = CallgrindImporter::new();
+        for profile in &self.profiles {
+            importer.read(BufReader::new(File::open(profile)?))?;
+        }
+
+        let alcov = importer.finish(
+            maps.as_ref(),
+            AlcovHeader::new(None::<PathBuf>, self.output.compress),
+        )?;
+        self.output.write(&alcov)
+    }
+}
@@ -0,0 +1,89 @@
+use clap::Args;
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::import::kcov::{
+    KcovImporter, kaslr_offset, parse_pc_list, parse_proc_modules, read_kcov,
+};
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovHeader, Error};
+
+use crate::import::ImportOutput;
+
+/// Import Linux kernel coverage, from kcov buffers or syzkaller cover lists
+#[derive(Clone, Debug, Args)]
+pub struct ImportKcov {
+    #[command(flatten)]
+    pub output: ImportOutput,
+    /// Kernel image, with symbols
+    #[arg(short, long)]
+    pub vmlinux: PathBuf,
+    /// KASLR offset of the kernel, in hexadecimal
+    #[arg(long, value_parser = parse_hex, conflicts_with = "kallsyms")]
+    pub kaslr_offset: Option<u64>,
+    /// `/proc/kallsyms` dump, to compute the KASLR offset
+    #[arg(long)]
+    pub kallsyms: Option<PathBuf>,
+    /// `/proc/modules` dump, giving the load address of kernel modules
+    #[arg(long, requires = "ko")]
+    pub proc_modules: Option<PathBuf>,
+    /// Kernel module (`.ko`) to map PCs to, as named in `/proc/modules`
+    #[arg(long)]
+    pub ko: Vec<PathBuf>,
+    /// PC files are syzkaller cover lists instead of raw kcov buffers. Block counters are
+    /// then unknown
+    #[arg(short, long)]
+    pub syzkaller: bool,
+    /// PC files
+    #[arg(required = true)]
+    pub pcs: Vec<PathBuf>,
+}
+
+fn parse_hex(hex: &str) -> Result<u64, String> {
+    u64::from_str_radix(hex.strip_prefix("0x").unwrap_or(hex), 16).map_err(|err| err.to_string())
+}
+
+impl ImportKcov {
+    pub fn run(self) -> Result<(), Error> {
+        let vmlinux = fs::read(&self.vmlinux)?;
+        let kaslr_offset = match (&self.kallsyms, self.kaslr_offset) {
+            (Some(kallsyms), _) => kaslr_offset(&vmlinux, &fs::read_to_string(kallsyms)?)?,
+            (None, offset) => offset.unwrap_or(0),
+        };
+
+        let mut importer = KcovImporter::new(&self.vmlinux, &vmlinux, kaslr_offset)?;
+
+        if let Some(proc_modules) = &self.proc_modules {
+            let proc_modules = parse_proc_modules(&fs::read_to_string(proc_modules)?);
+
+            for ko in &self.ko {
+                // module names use underscores, whatever the file name.
+                let name = ko
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().replace('-', "_"));
+
+                match proc_modules
+                    .iter()
+                    .find(|module| Some(&module.name) == name.as_ref())
+                {
+                    Some(module) => importer.add_module(ko, module)?,
+                    None => eprintln!("{}: not loaded, skipping.", ko.display()),
+                }
+            }
+        }
+
+        for pcs in &self.pcs {
+            let pcs_list = if self.syzkaller {
+                parse_pc_list(&fs::read_to_string(pcs)?, importer.is_64())
+            } else {
+                read_kcov(&mut File::open(pcs)?)?
+            };
+
+            importer.add_pcs(&pcs_list, !self.syzkaller)?;
+        }
+
+        let alcov = importer.finish(AlcovHeader::new(None::<PathBuf>, self.output.compress));
+        self.output.write(&alcov)
+    }
+}
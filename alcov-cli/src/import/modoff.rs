@@ -0,0 +1,31 @@
+use clap::Args;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, AlcovHeader, Error};
+use clap_stdin::FileOrStdin;
+
+use crate::import::ImportOutput;
+
+/// Import a "module+offset" text file, one `<module>+<offset>[:<size>[:<count>]]` block per line
+#[derive(Clone, Debug, Args)]
+pub struct ImportModOff {
+    #[command(flatten)]
+    pub output: ImportOutput,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+impl ImportModOff {
+    pub fn run(self) -> Result<(), Error> {
+        let input_rdr = BufReader::new(self.input.into_reader().unwrap());
+
+        let alcov = Alcov::read_modoff(
+            input_rdr,
+            AlcovHeader::new(None::<PathBuf>, self.output.compress),
+        )?;
+        self.output.write(&alcov)
+    }
+}
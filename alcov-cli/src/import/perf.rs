@@ -0,0 +1,45 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::import::perf::PerfScriptImporter;
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovHeader, Error, ProcMaps};
+
+use crate::import::ImportOutput;
+
+/// Import `perf script -F ip,brstack --show-mmap-events` output as sampled edge coverage
+#[derive(Clone, Debug, Args)]
+pub struct ImportPerf {
+    #[command(flatten)]
+    pub output: ImportOutput,
+    /// Memory mappings, in the `/proc/<pid>/maps` format. By default, the mmap events found
+    /// in the output are used
+    #[arg(short, long)]
+    pub maps: Option<PathBuf>,
+    /// `perf script` output files
+    #[arg(required = true)]
+    pub scripts: Vec<PathBuf>,
+}
+
+impl ImportPerf {
+    pub fn run(self) -> Result<(), Error> {
+        let maps = match &self.maps {
+            Some(maps) => Some(ProcMaps::read(&mut File::open(maps)?)?),
+            None => None,
+        };
+
+        let mut importer = PerfScriptImporter::new();
+        for script in &self.scripts {
+            importer.read(BufReader::new(File::open(script)?))?;
+        }
+
+        let alcov = importer.finish(
+            maps.as_ref(),
+            AlcovHeader::new(None::<PathBuf>, self.output.compress),
+        )?;
+        self.output.write(&alcov)
+    }
+}
@@ -0,0 +1,45 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::import::qemu::QemuLogImporter;
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovHeader, Error, ProcMaps};
+
+use crate::import::ImportOutput;
+
+/// Import QEMU `-d exec`, `-d in_asm` and `-d page` logs
+#[derive(Clone, Debug, Args)]
+pub struct ImportQemu {
+    #[command(flatten)]
+    pub output: ImportOutput,
+    /// Guest memory mappings, in the `/proc/<pid>/maps` format. By default, the `-d page`
+    /// layout found in the logs is used
+    #[arg(short, long)]
+    pub maps: Option<PathBuf>,
+    /// QEMU log files
+    #[arg(required = true)]
+    pub logs: Vec<PathBuf>,
+}
+
+impl ImportQemu {
+    pub fn run(self) -> Result<(), Error> {
+        let maps = match &self.maps {
+            Some(maps) => Some(ProcMaps::read(&mut File::open(maps)?)?),
+            None => None,
+        };
+
+        let mut importer = QemuLogImporter::new();
+        for log in &self.logs {
+            importer.read(BufReader::new(File::open(log)?))?;
+        }
+
+        let alcov = importer.finish(
+            maps.as_ref(),
+            AlcovHeader::new(None::<PathBuf>, self.output.compress),
+        )?;
+        self.output.write(&alcov)
+    }
+}
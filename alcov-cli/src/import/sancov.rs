@@ -0,0 +1,50 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "v0")]
+use alcov::v0::import::sancov::{Sancov, SancovImporter, is_sancov_of};
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovHeader, Error};
+
+use crate::import::ImportOutput;
+
+/// Import LLVM SanitizerCoverage `.sancov` files
+#[derive(Clone, Debug, Args)]
+pub struct ImportSancov {
+    #[command(flatten)]
+    pub output: ImportOutput,
+    /// Binaries for which coverage has been dumped. sancov files are matched by file name
+    #[arg(short, long = "binary", required = true)]
+    pub binaries: Vec<PathBuf>,
+    /// sancov files, named `<module>.<pid>.sancov`
+    #[arg(required = true)]
+    pub sancov: Vec<PathBuf>,
+}
+
+impl ImportSancov {
+    pub fn run(self) -> Result<(), Error> {
+        let mut importer = SancovImporter::new();
+
+        for sancov_path in &self.sancov {
+            let Some(binary) = self.binary_of(sancov_path) else {
+                eprintln!("{}: no matching binary, skipping.", sancov_path.display());
+                continue;
+            };
+
+            let mut reader = BufReader::new(File::open(sancov_path)?);
+            let sancov = Sancov::read(&mut reader)?;
+            importer.add(binary, &sancov)?;
+        }
+
+        let alcov = importer.finish(AlcovHeader::new(None::<PathBuf>, self.output.compress));
+        self.output.write(&alcov)
+    }
+
+    fn binary_of(&self, sancov_path: &Path) -> Option<&PathBuf> {
+        self.binaries
+            .iter()
+            .find(|binary| is_sancov_of(sancov_path, binary))
+    }
+}
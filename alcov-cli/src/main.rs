@@ -1,8 +1,31 @@
+use crate::convert::Convert;
+use crate::db::Db;
 use crate::dump::Dump;
+use crate::extract_input::ExtractInput;
+use crate::fix_modules::FixModules;
+use crate::graph::Graph;
+use crate::import::Import;
+use crate::merge::Merge;
+use crate::recompress::Recompress;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+use crate::record::Record;
+use crate::upgrade::Upgrade;
+use crate::verify::Verify;
 use clap::{Parser, Subcommand};
 
+pub mod convert;
+pub mod db;
 pub mod dump;
+pub mod extract_input;
+pub mod fix_modules;
+pub mod graph;
+pub mod import;
 pub mod merge;
+pub mod recompress;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+pub mod record;
+pub mod upgrade;
+pub mod verify;
 
 #[derive(Clone, Debug, Parser)]
 pub struct Cli {
@@ -12,15 +35,61 @@ pub struct Cli {
 
 #[derive(Clone, Debug, Subcommand)]
 pub enum Commands {
+    Convert(Convert),
+    Db(Db),
     Dump(Dump),
+    ExtractInput(ExtractInput),
+    FixModules(FixModules),
+    Graph(Graph),
+    Import(Import),
+    Merge(Merge),
+    Recompress(Recompress),
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    Record(Record),
+    Upgrade(Upgrade),
+    Verify(Verify),
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
+        Commands::Convert(convert) => {
+            convert.run().unwrap();
+        }
+        Commands::Db(db) => {
+            db.run().unwrap();
+        }
         Commands::Dump(dump) => {
             dump.run().unwrap();
         }
+        Commands::ExtractInput(extract_input) => {
+            extract_input.run().unwrap();
+        }
+        Commands::FixModules(fix_modules) => {
+            fix_modules.run().unwrap();
+        }
+        Commands::Graph(graph) => {
+            graph.run().unwrap();
+        }
+        Commands::Import(import) => {
+            import.run().unwrap();
+        }
+        Commands::Merge(merge) => {
+            merge.run().unwrap();
+        }
+        Commands::Recompress(recompress) => {
+            recompress.run().unwrap();
+        }
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        Commands::Record(record) => {
+            record.run().unwrap();
+        }
+        Commands::Upgrade(upgrade) => {
+            upgrade.run().unwrap();
+        }
+        Commands::Verify(verify) => {
+            verify.run().unwrap();
+        }
     }
 }
@@ -1 +1,69 @@
+use clap::Args;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
 
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, Error};
+
+/// Merge traces of the same program, adding up their hit counts
+///
+/// Modules are matched by path. Traces whose modules have the same path but come from
+/// different builds are refused.
+#[derive(Clone, Debug, Args)]
+pub struct Merge {
+    /// Output alcov file
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// alcov files to merge
+    #[arg(required = true)]
+    pub traces: Vec<PathBuf>,
+}
+
+impl Merge {
+    pub fn run(self) -> Result<(), Error> {
+        let mut merged: Option<Alcov> = None;
+
+        for trace in &self.traces {
+            let alcov = Alcov::read(&mut BufReader::new(File::open(trace)?))?;
+
+            let Some(merged) = &mut merged else {
+                merged = Some(alcov);
+                continue;
+            };
+
+            for module in &alcov.modules {
+                let Some(path) = &module.path else {
+                    continue;
+                };
+
+                let same_file = merged
+                    .modules
+                    .iter()
+                    .find(|merged_module| merged_module.path.as_ref() == Some(path))
+                    .map(|merged_module| merged_module.same_file(module));
+                if same_file == Some(None) {
+                    eprintln!(
+                        "{}: {} cannot be told apart from other builds, merging anyway.",
+                        trace.display(),
+                        path.display()
+                    );
+                }
+            }
+
+            if let Err(err) = merged.merge(&alcov) {
+                if let Error::ModuleIdentityMismatch(path) = &err {
+                    eprintln!(
+                        "{}: {} comes from another build than in the previous traces.",
+                        trace.display(),
+                        path.display()
+                    );
+                }
+                return Err(err);
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        merged.unwrap().write(&mut writer)
+    }
+}
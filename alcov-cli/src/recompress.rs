@@ -0,0 +1,53 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{Alcov, AlcovChecksum, AlcovCodec, Error};
+use clap_stdin::FileOrStdin;
+
+/// Rewrite an alcov file with other compression codecs, in the latest version of the format
+#[derive(Clone, Debug, Args)]
+pub struct Recompress {
+    /// Codec of every chunk (none, lzma2, zstd or lz4)
+    #[arg(short, long)]
+    pub codec: AlcovCodec,
+    /// Codec of the blocks chunk, instead of the one given by `--codec`
+    #[arg(long)]
+    pub blocks_codec: Option<AlcovCodec>,
+    /// Codec of the edges chunk, instead of the one given by `--codec`
+    #[arg(long)]
+    pub edges_codec: Option<AlcovCodec>,
+    /// Checksum algorithm of the integrity section (crc32c or xxh64)
+    #[arg(long, default_value = "crc32c")]
+    pub checksum: AlcovChecksum,
+    /// Do not write an integrity section
+    #[arg(long, conflicts_with = "checksum")]
+    pub no_checksum: bool,
+    /// Output alcov file
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+impl Recompress {
+    pub fn run(self) -> Result<(), Error> {
+        let mut input_rdr = self.input.into_reader().unwrap();
+        let mut alcov = Alcov::read(&mut input_rdr)?;
+
+        alcov.hdr.set_codec(self.codec);
+        if let Some(codec) = self.blocks_codec {
+            alcov.hdr.blocks_codec = codec;
+        }
+        if let Some(codec) = self.edges_codec {
+            alcov.hdr.edges_codec = codec;
+        }
+        alcov.hdr.checksum = (!self.no_checksum).then_some(self.checksum);
+
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        alcov.write(&mut writer)
+    }
+}
@@ -0,0 +1,136 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufWriter;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::SystemTime;
+use std::{env, fs};
+
+use alcov::v0::metadata::keys;
+use alcov::v0::{AlcovHeader, AlcovInput, AlcovValue, Error};
+use nix::sys::ptrace;
+use nix::sys::wait::{WaitStatus, waitpid};
+use nix::unistd::Pid;
+
+use crate::record::tracer::Tracer;
+
+mod disasm;
+mod tracer;
+
+/// Record the coverage of a program, using breakpoints
+#[derive(Clone, Debug, Args)]
+pub struct Record {
+    /// Output alcov file
+    #[arg(short, long, default_value = "trace.alcov")]
+    pub output: PathBuf,
+    /// Also trace the libraries whose file name starts with this name (e.g. libc.so)
+    #[arg(short, long = "lib")]
+    pub libs: Vec<String>,
+    /// Keep breakpoints after their first hit, to count how many times blocks are taken
+    #[arg(short, long)]
+    pub persistent: bool,
+    /// Record edges between consecutive blocks of a thread
+    #[arg(short, long, requires = "persistent")]
+    pub edges: bool,
+    /// Record the coverage of each thread
+    #[arg(short, long, requires = "persistent")]
+    pub threads: bool,
+    /// Compress the output file
+    #[arg(short, long)]
+    pub compress: bool,
+    /// Path of the input given to the program
+    #[arg(short, long)]
+    pub input: Option<PathBuf>,
+    /// Embed the content of the input in the trace
+    #[arg(long, requires = "input")]
+    pub embed_input: bool,
+    /// Record the environment of the program in the metadata
+    #[arg(long)]
+    pub record_env: bool,
+    /// Additional metadata entry, as KEY=VALUE (e.g. campaign_id=nightly-42)
+    #[arg(short, long = "metadata", value_parser = parse_metadata)]
+    pub metadata: Vec<(String, String)>,
+    /// Program to run, followed by its arguments
+    #[arg(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+impl Record {
+    pub fn run(self) -> Result<(), Error> {
+        let mut command = Command::new(&self.command[0]);
+        command.args(&self.command[1..]);
+
+        // SAFETY: only async-signal-safe calls are performed in the child.
+        unsafe {
+            command.pre_exec(|| ptrace::traceme().map_err(std::io::Error::from));
+        }
+
+        // the input is read before the program can modify it.
+        let input = match &self.input {
+            Some(input) if self.embed_input => Some(AlcovInput::from_file(input)?),
+            _ => None,
+        };
+
+        let start_time = SystemTime::now();
+        let child = command.spawn()?;
+        let pid = Pid::from_raw(child.id() as i32);
+
+        // the child stops on its execve.
+        waitpid(pid, None).map_err(std::io::Error::from)?;
+
+        let mut tracer = Tracer::new(pid, self.libs, self.persistent, self.edges, self.threads)?;
+        let exit = tracer.run()?;
+        let end_time = SystemTime::now();
+
+        let mut hdr = AlcovHeader::new(self.input, self.compress);
+        hdr.input = input;
+        hdr.set_metadata(keys::TRACER, AlcovValue::String("alcov".to_string()));
+        hdr.set_metadata(
+            keys::TRACER_VERSION,
+            AlcovValue::String(env!("CARGO_PKG_VERSION").to_string()),
+        );
+        hdr.set_metadata(keys::COMMAND, AlcovValue::Strings(self.command));
+        if self.record_env {
+            let environment = env::vars_os()
+                .map(|(name, value)| format!("{}={}", name.display(), value.display()))
+                .collect();
+            hdr.set_metadata(keys::ENVIRONMENT, AlcovValue::Strings(environment));
+        }
+        if let Ok(hostname) = fs::read_to_string("/proc/sys/kernel/hostname") {
+            hdr.set_metadata(
+                keys::HOSTNAME,
+                AlcovValue::String(hostname.trim().to_string()),
+            );
+        }
+        hdr.set_metadata(keys::START_TIME, AlcovValue::timestamp(start_time));
+        hdr.set_metadata(keys::END_TIME, AlcovValue::timestamp(end_time));
+        match exit {
+            Some(WaitStatus::Exited(_, status)) => {
+                hdr.set_metadata(keys::EXIT_STATUS, AlcovValue::Int(status.into()));
+            }
+            Some(WaitStatus::Signaled(_, signal, _)) => {
+                hdr.set_metadata(keys::EXIT_SIGNAL, AlcovValue::Int(signal as i64));
+            }
+            _ => {}
+        }
+        for (key, value) in self.metadata {
+            hdr.set_metadata(key, AlcovValue::String(value));
+        }
+
+        let alcov = tracer.coverage(hdr)?;
+
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        alcov.write(&mut writer)?;
+
+        Ok(())
+    }
+}
+
+fn parse_metadata(entry: &str) -> Result<(String, String), String> {
+    let (key, value) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("no `=` in `{entry}`"))?;
+
+    Ok((key.to_string(), value.to_string()))
+}
@@ -0,0 +1,117 @@
+use alcov::v0::Error;
+use iced_x86::{Decoder, DecoderOptions, FlowControl, Instruction};
+use object::{Object, ObjectSection, ObjectSegment, ObjectSymbol, SectionKind};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// A basic block found by static disassembly.
+#[derive(Debug, Clone)]
+pub struct StaticBlock {
+    /// virtual address of the block, as found in the binary.
+    pub address: u64,
+    pub size: u32,
+    pub nb_instructions: u32,
+}
+
+/// Static view of an executable binary.
+#[derive(Debug, Clone)]
+pub struct StaticModule {
+    /// lowest (page-aligned) virtual address of the loadable segments.
+    pub image_base: u64,
+    pub blocks: Vec<StaticBlock>,
+}
+
+struct TextSection {
+    address: u64,
+    instructions: Vec<Instruction>,
+}
+
+impl StaticModule {
+    /// Disassemble every text section of an x86-64 binary, and split it in basic blocks.
+    ///
+    /// Block leaders are the section starts, the function symbols, the targets of direct
+    /// branches and the instructions following a control flow instruction.
+    pub fn disassemble(path: &Path) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        let file = object::File::parse(&*data).map_err(|_| Error::MalformedBinary)?;
+
+        if file.architecture() != object::Architecture::X86_64 {
+            return Err(Error::MalformedBinary);
+        }
+
+        let image_base = file
+            .segments()
+            .map(|segment| segment.address() & !0xfff)
+            .min()
+            .ok_or(Error::MalformedBinary)?;
+
+        let mut sections: Vec<TextSection> = Vec::new();
+        let mut leaders: BTreeSet<u64> = BTreeSet::new();
+
+        for section in file.sections() {
+            if section.kind() != SectionKind::Text {
+                continue;
+            }
+
+            let bytes = section.data().map_err(|_| Error::MalformedBinary)?;
+            let mut decoder = Decoder::with_ip(64, bytes, section.address(), DecoderOptions::NONE);
+            let mut instructions: Vec<Instruction> = Vec::new();
+
+            leaders.insert(section.address());
+
+            while decoder.can_decode() {
+                let instruction = decoder.decode();
+
+                match instruction.flow_control() {
+                    FlowControl::Next => {}
+                    FlowControl::UnconditionalBranch
+                    | FlowControl::ConditionalBranch
+                    | FlowControl::Call => {
+                        leaders.insert(instruction.near_branch_target());
+                        leaders.insert(instruction.next_ip());
+                    }
+                    _ => {
+                        leaders.insert(instruction.next_ip());
+                    }
+                }
+
+                instructions.push(instruction);
+            }
+
+            sections.push(TextSection {
+                address: section.address(),
+                instructions,
+            });
+        }
+
+        for symbol in file.symbols() {
+            if symbol.kind() == object::SymbolKind::Text && symbol.address() != 0 {
+                leaders.insert(symbol.address());
+            }
+        }
+
+        let mut blocks: Vec<StaticBlock> = Vec::new();
+        for section in &sections {
+            let mut block_start = section.address;
+            let mut nb_instructions: u32 = 0;
+
+            for instruction in &section.instructions {
+                let next_ip = instruction.next_ip();
+                nb_instructions += 1;
+
+                if instruction.flow_control() != FlowControl::Next || leaders.contains(&next_ip) {
+                    blocks.push(StaticBlock {
+                        address: block_start,
+                        size: u32::try_from(next_ip - block_start)?,
+                        nb_instructions,
+                    });
+                    block_start = next_ip;
+                    nb_instructions = 0;
+                }
+            }
+        }
+
+        Ok(Self { image_base, blocks })
+    }
+}
@@ -0,0 +1,617 @@
+use crate::record::disasm::StaticModule;
+use alcov::v0::{
+    Alcov, AlcovBlock, AlcovBlockEdges, AlcovDstBlockEdgeMetadata, AlcovEdges, AlcovHeader,
+    AlcovModule, AlcovThread, Error, ProcMaps,
+};
+use nix::errno::Errno;
+use nix::libc;
+use nix::sys::ptrace;
+use nix::sys::signal::Signal;
+use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+use nix::unistd::Pid;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ffi::c_void;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const INT3: i64 = 0xcc;
+const SYS_MMAP: u64 = 9;
+
+fn sys<T>(res: nix::Result<T>) -> Result<T, Error> {
+    res.map_err(|errno| Error::Io(io::Error::from(errno)))
+}
+
+/// Thread group of a task, if it is still alive.
+fn thread_group(tid: Pid) -> Option<Pid> {
+    let status = fs::read_to_string(format!("/proc/{tid}/status")).ok()?;
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(str::trim)
+    };
+
+    // a zombie thread never reports a stop.
+    if field("State:")?.starts_with('Z') {
+        return None;
+    }
+
+    field("Tgid:")?.parse().ok().map(Pid::from_raw)
+}
+
+/// module index and block index in that module.
+type BlockRef = (usize, usize);
+
+struct Breakpoint {
+    module_id: usize,
+    block_id: usize,
+    orig_word: i64,
+}
+
+struct TracedModule {
+    module: AlcovModule,
+    /// runtime address, size and number of instructions of each block.
+    blocks: Vec<(u64, u32, u32)>,
+    nb_taken: Vec<u64>,
+}
+
+/// A task created by a traced one, known from the ptrace event of its parent.
+#[derive(Clone, Copy)]
+enum NewTask {
+    Thread,
+    /// a process with its own copy of the memory, and of the breakpoints.
+    Fork,
+    /// a process sharing the memory of its parent until it calls `execve`.
+    Vfork,
+}
+
+/// Blocks executed and edges taken by a thread.
+#[derive(Default)]
+struct ThreadCoverage {
+    name: Option<String>,
+    nb_taken: HashMap<BlockRef, u64>,
+    edges_taken: HashMap<(BlockRef, BlockRef), u64>,
+}
+
+/// A process traced with breakpoints on every basic block of the selected modules.
+pub struct Tracer {
+    pid: Pid,
+    exe: PathBuf,
+    libs: Vec<String>,
+    persistent: bool,
+    edges: bool,
+    per_thread: bool,
+    /// traced tasks sharing the memory of the program: its threads, and vfork children.
+    threads: HashSet<Pid>,
+    /// parent of each running vfork child. it does not run until the child execs or exits.
+    vfork_parents: HashMap<Pid, Pid>,
+    /// new tasks whose initial stop has not been seen yet.
+    new_tasks: HashMap<Pid, NewTask>,
+    /// new tasks whose initial stop was seen before the event of their parent.
+    early_stops: HashSet<Pid>,
+    /// statuses received while stopping threads, to handle before waiting again.
+    pending: VecDeque<WaitStatus>,
+    /// threads with a SIGSTOP sent by the tracer still to be received.
+    interrupted: HashSet<Pid>,
+    modules: Vec<TracedModule>,
+    breakpoints: HashMap<u64, Breakpoint>,
+    /// addresses of the breakpoints removed after their first hit.
+    lifted: HashSet<u64>,
+    last_block: HashMap<Pid, BlockRef>,
+    edges_taken: HashMap<(BlockRef, BlockRef), u64>,
+    thread_coverage: BTreeMap<Pid, ThreadCoverage>,
+}
+
+impl Tracer {
+    /// Start tracing a process stopped right after its `execve`.
+    pub fn new(
+        pid: Pid,
+        libs: Vec<String>,
+        persistent: bool,
+        edges: bool,
+        per_thread: bool,
+    ) -> Result<Self, Error> {
+        sys(ptrace::setoptions(
+            pid,
+            ptrace::Options::PTRACE_O_EXITKILL
+                | ptrace::Options::PTRACE_O_TRACECLONE
+                | ptrace::Options::PTRACE_O_TRACEFORK
+                | ptrace::Options::PTRACE_O_TRACEVFORK
+                | ptrace::Options::PTRACE_O_TRACEEXEC
+                | ptrace::Options::PTRACE_O_TRACESYSGOOD,
+        ))?;
+
+        let exe = fs::read_link(format!("/proc/{pid}/exe"))?;
+
+        Ok(Self {
+            pid,
+            exe,
+            libs,
+            persistent,
+            edges,
+            per_thread,
+            threads: HashSet::from([pid]),
+            vfork_parents: HashMap::new(),
+            new_tasks: HashMap::new(),
+            early_stops: HashSet::new(),
+            pending: VecDeque::new(),
+            interrupted: HashSet::new(),
+            modules: Vec::new(),
+            breakpoints: HashMap::new(),
+            lifted: HashSet::new(),
+            last_block: HashMap::new(),
+            edges_taken: HashMap::new(),
+            thread_coverage: BTreeMap::new(),
+        })
+    }
+
+    fn is_selected(&self, path: &Path) -> bool {
+        if path == self.exe {
+            return true;
+        }
+
+        if !path.is_absolute() {
+            return false;
+        }
+
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+
+        self.libs
+            .iter()
+            .any(|lib| file_name.starts_with(lib.as_str()))
+    }
+
+    /// Look for newly mapped modules in `/proc/<pid>/maps`, and instrument them.
+    ///
+    /// A module is only instrumented once its executable mapping is present, since the
+    /// loader may still remap the file over the breakpoints before that.
+    fn scan_modules(&mut self) -> Result<(), Error> {
+        let maps = ProcMaps::from_pid(self.pid.as_raw() as u32)?;
+
+        for module in maps.modules() {
+            let Some(path) = &module.path else {
+                continue;
+            };
+
+            let executable = maps
+                .entries
+                .iter()
+                .any(|entry| entry.perms.execute && entry.module_path().as_ref() == Some(path));
+
+            if executable
+                && self.is_selected(path)
+                && !self
+                    .modules
+                    .iter()
+                    .any(|traced| traced.module.path == module.path)
+            {
+                self.instrument(module)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn instrument(&mut self, maps_module: AlcovModule) -> Result<(), Error> {
+        // modules built from maps always have a path.
+        let path = maps_module.path.unwrap();
+        let static_module = StaticModule::disassemble(&path)?;
+
+        // the loader may not have mapped every segment yet, take them from the file.
+        let module = AlcovModule::from_elf(path, maps_module.base_address)?;
+        let bias = module.base_address.wrapping_sub(static_module.image_base);
+
+        let module_id = self.modules.len();
+        let mut blocks: Vec<(u64, u32, u32)> = Vec::new();
+
+        for block in &static_module.blocks {
+            let address = block.address.wrapping_add(bias);
+
+            let offset = address.wrapping_sub(module.base_address);
+            if !module
+                .segments
+                .iter()
+                .any(|segment| segment.module_range.contains(&offset))
+            {
+                continue;
+            }
+
+            let orig_word = sys(ptrace::read(self.pid, address as *mut c_void))?;
+            sys(ptrace::write(
+                self.pid,
+                address as *mut c_void,
+                (orig_word & !0xff) | INT3,
+            ))?;
+
+            self.breakpoints.insert(
+                address,
+                Breakpoint {
+                    module_id,
+                    block_id: blocks.len(),
+                    orig_word,
+                },
+            );
+
+            blocks.push((address, block.size, block.nb_instructions));
+        }
+
+        self.modules.push(TracedModule {
+            module,
+            nb_taken: vec![0; blocks.len()],
+            blocks,
+        });
+
+        Ok(())
+    }
+
+    fn resume(&self, pid: Pid, sig: Option<Signal>) -> Result<(), Error> {
+        // syscalls are only traced to follow library loads.
+        if self.libs.is_empty() {
+            sys(ptrace::cont(pid, sig))
+        } else {
+            sys(ptrace::syscall(pid, sig))
+        }
+    }
+
+    /// Handle a breakpoint hit. returns false if the trap was not caused by one of our
+    /// breakpoints.
+    fn hit(&mut self, pid: Pid) -> Result<bool, Error> {
+        let mut regs = sys(ptrace::getregs(pid))?;
+        let address = regs.rip - 1;
+
+        let Some(bp) = self.breakpoints.get(&address) else {
+            if !self.lifted.contains(&address) {
+                return Ok(false);
+            }
+
+            // another thread trapped on the breakpoint before it was removed: it runs the
+            // original instruction, without counting the block again.
+            regs.rip = address;
+            sys(ptrace::setregs(pid, regs))?;
+            return Ok(true);
+        };
+
+        let (module_id, block_id, orig_word) = (bp.module_id, bp.block_id, bp.orig_word);
+        self.modules[module_id].nb_taken[block_id] += 1;
+
+        let block = (module_id, block_id);
+        let src = match self.edges {
+            true => self.last_block.insert(pid, block),
+            false => None,
+        };
+        if let Some(src) = src {
+            *self.edges_taken.entry((src, block)).or_default() += 1;
+        }
+
+        if self.per_thread {
+            // the name is read on the first hit, as it is gone once the thread exits.
+            let thread = self
+                .thread_coverage
+                .entry(pid)
+                .or_insert_with(|| ThreadCoverage {
+                    name: fs::read_to_string(format!("/proc/{pid}/comm"))
+                        .ok()
+                        .map(|name| name.trim_end().to_string()),
+                    ..Default::default()
+                });
+
+            *thread.nb_taken.entry(block).or_default() += 1;
+            if let Some(src) = src {
+                *thread.edges_taken.entry((src, block)).or_default() += 1;
+            }
+        }
+
+        // other threads could run the block uncounted while the breakpoint is lifted.
+        let stopped = match self.persistent {
+            true => self.stop_threads(pid)?,
+            false => Vec::new(),
+        };
+
+        // restore the original instruction and execute it.
+        let word = sys(ptrace::read(pid, address as *mut c_void))?;
+        sys(ptrace::write(
+            pid,
+            address as *mut c_void,
+            (word & !0xff) | (orig_word & 0xff),
+        ))?;
+        regs.rip = address;
+        sys(ptrace::setregs(pid, regs))?;
+
+        if self.persistent {
+            let exited = loop {
+                sys(ptrace::step(pid, None))?;
+
+                match sys(waitpid(pid, Some(WaitPidFlag::__WALL)))? {
+                    // a SIGSTOP of the tracer can be delivered before the step.
+                    WaitStatus::Stopped(_, Signal::SIGSTOP) if self.interrupted.remove(&pid) => {}
+                    WaitStatus::Stopped(_, Signal::SIGTRAP) => break false,
+                    // a signal raised by the instruction, an event or an exit is handled
+                    // by `run`, like any other status.
+                    status @ (WaitStatus::Exited(..) | WaitStatus::Signaled(..)) => {
+                        self.pending.push_back(status);
+                        break true;
+                    }
+                    status => {
+                        self.pending.push_back(status);
+                        break false;
+                    }
+                }
+            };
+
+            // once the task is gone, the breakpoint is put back through another thread, if
+            // the whole process has not exited.
+            let rearm_pid = match exited {
+                true => stopped.first().copied(),
+                false => Some(pid),
+            };
+            if let Some(rearm_pid) = rearm_pid {
+                let rearm = || -> nix::Result<()> {
+                    let word = ptrace::read(rearm_pid, address as *mut c_void)?;
+                    ptrace::write(rearm_pid, address as *mut c_void, (word & !0xff) | INT3)
+                };
+
+                match rearm() {
+                    Err(Errno::ESRCH) if exited => {}
+                    res => sys(res)?,
+                }
+            }
+
+            for tid in stopped {
+                match self.resume(tid, None) {
+                    // the other threads die with the process.
+                    Err(_) if exited => {}
+                    res => res?,
+                }
+            }
+        } else {
+            self.breakpoints.remove(&address);
+            self.lifted.insert(address);
+        }
+
+        Ok(true)
+    }
+
+    /// Stop the running threads other than `pid`, returning the ones to resume afterwards.
+    ///
+    /// Threads reporting another event while being stopped are left stopped, their event is
+    /// handled later by [`Tracer::run`].
+    fn stop_threads(&mut self, pid: Pid) -> Result<Vec<Pid>, Error> {
+        let running: Vec<(Pid, Pid)> = self
+            .threads
+            .iter()
+            .filter(|&&tid| {
+                tid != pid
+                    && !self.pending.iter().any(|status| status.pid() == Some(tid))
+                    && !self.vfork_parents.values().any(|&parent| parent == tid)
+            })
+            .filter_map(|&tid| thread_group(tid).map(|tgid| (tgid, tid)))
+            .collect();
+
+        for &(tgid, tid) in &running {
+            // SAFETY: tgkill only sends a signal, to a thread of ours.
+            let res = unsafe {
+                libc::syscall(libc::SYS_tgkill, tgid.as_raw(), tid.as_raw(), libc::SIGSTOP)
+            };
+            sys(Errno::result(res))?;
+        }
+
+        let mut stopped: Vec<Pid> = Vec::new();
+        for (_, tid) in running {
+            match sys(waitpid(tid, Some(WaitPidFlag::__WALL)))? {
+                WaitStatus::Stopped(_, Signal::SIGSTOP) => stopped.push(tid),
+                status @ (WaitStatus::Exited(..) | WaitStatus::Signaled(..)) => {
+                    self.pending.push_back(status);
+                }
+                status => {
+                    self.interrupted.insert(tid);
+                    self.pending.push_back(status);
+                }
+            }
+        }
+
+        Ok(stopped)
+    }
+
+    /// Let a new task run, once both its initial stop and the event of its parent are seen.
+    fn start_task(&mut self, pid: Pid, task: NewTask) -> Result<(), Error> {
+        match task {
+            NewTask::Thread | NewTask::Vfork => {
+                self.threads.insert(pid);
+                self.resume(pid, None)
+            }
+            NewTask::Fork => {
+                // the child is not traced: its copy of the breakpoints is removed.
+                for (address, bp) in &self.breakpoints {
+                    let word = sys(ptrace::read(pid, *address as *mut c_void))?;
+                    sys(ptrace::write(
+                        pid,
+                        *address as *mut c_void,
+                        (word & !0xff) | (bp.orig_word & 0xff),
+                    ))?;
+                }
+
+                sys(ptrace::detach(pid, None))
+            }
+        }
+    }
+
+    /// Run the traced process until every thread exits, returning how the process ended.
+    pub fn run(&mut self) -> Result<Option<WaitStatus>, Error> {
+        self.scan_modules()?;
+        self.resume(self.pid, None)?;
+
+        let mut exit: Option<WaitStatus> = None;
+
+        while !self.threads.is_empty() {
+            let status = match self.pending.pop_front() {
+                Some(status) => status,
+                None => sys(waitpid(None, Some(WaitPidFlag::__WALL)))?,
+            };
+
+            match status {
+                status @ (WaitStatus::Exited(pid, _) | WaitStatus::Signaled(pid, _, _)) => {
+                    if pid == self.pid {
+                        exit = Some(status);
+                    }
+                    self.threads.remove(&pid);
+                    self.vfork_parents.remove(&pid);
+                    self.last_block.remove(&pid);
+                }
+                WaitStatus::Stopped(pid, Signal::SIGTRAP) => {
+                    if self.hit(pid)? {
+                        self.resume(pid, None)?;
+                    } else {
+                        self.resume(pid, Some(Signal::SIGTRAP))?;
+                    }
+                }
+                WaitStatus::Stopped(pid, Signal::SIGSTOP) if !self.threads.contains(&pid) => {
+                    // initial stop of a new task, it can be reported before the parent event.
+                    match self.new_tasks.remove(&pid) {
+                        Some(task) => self.start_task(pid, task)?,
+                        None => {
+                            self.early_stops.insert(pid);
+                        }
+                    }
+                }
+                WaitStatus::Stopped(pid, Signal::SIGSTOP) if self.interrupted.remove(&pid) => {
+                    self.resume(pid, None)?;
+                }
+                WaitStatus::Stopped(pid, sig) => {
+                    self.resume(pid, Some(sig))?;
+                }
+                WaitStatus::PtraceEvent(pid, _, event) => {
+                    let task = match event {
+                        libc::PTRACE_EVENT_CLONE => Some(NewTask::Thread),
+                        libc::PTRACE_EVENT_FORK => Some(NewTask::Fork),
+                        libc::PTRACE_EVENT_VFORK => Some(NewTask::Vfork),
+                        _ => None,
+                    };
+
+                    if let Some(task) = task {
+                        let new_pid = Pid::from_raw(i32::try_from(sys(ptrace::getevent(pid))?)?);
+                        if let NewTask::Vfork = task {
+                            self.vfork_parents.insert(new_pid, pid);
+                        }
+                        if self.early_stops.remove(&new_pid) {
+                            self.start_task(new_pid, task)?;
+                        } else {
+                            self.new_tasks.insert(new_pid, task);
+                        }
+                    } else if event == libc::PTRACE_EVENT_EXEC && pid != self.pid {
+                        // a vfork child no longer shares the memory of the program.
+                        self.threads.remove(&pid);
+                        self.vfork_parents.remove(&pid);
+                        self.last_block.remove(&pid);
+                        if self.interrupted.remove(&pid) {
+                            // the SIGSTOP of the tracer is received before detaching.
+                            sys(ptrace::cont(pid, None))?;
+                            sys(waitpid(pid, Some(WaitPidFlag::__WALL)))?;
+                        }
+                        sys(ptrace::detach(pid, None))?;
+                        continue;
+                    }
+
+                    self.resume(pid, None)?;
+                }
+                WaitStatus::PtraceSyscall(pid) => {
+                    let regs = sys(ptrace::getregs(pid))?;
+                    if regs.orig_rax == SYS_MMAP {
+                        self.scan_modules()?;
+                    }
+                    self.resume(pid, None)?;
+                }
+                WaitStatus::Continued(_) | WaitStatus::StillAlive => {}
+            }
+        }
+
+        Ok(exit)
+    }
+
+    /// Build the coverage of the executed blocks.
+    pub fn coverage(&self, hdr: AlcovHeader) -> Result<Alcov, Error> {
+        let mut modules: Vec<AlcovModule> = Vec::new();
+        let mut blocks: Vec<AlcovBlock> = Vec::new();
+        let mut block_ids: HashMap<BlockRef, u64> = HashMap::new();
+
+        for (module_id, traced) in self.modules.iter().enumerate() {
+            let module = &traced.module;
+
+            for (block_id, (address, size, nb_instructions)) in traced.blocks.iter().enumerate() {
+                let nb_taken = traced.nb_taken[block_id];
+                if nb_taken == 0 {
+                    continue;
+                }
+
+                let offset = address - module.base_address;
+                let Some(segment_id) = module
+                    .segments
+                    .iter()
+                    .position(|segment| segment.module_range.contains(&offset))
+                else {
+                    continue;
+                };
+
+                block_ids.insert((module_id, block_id), blocks.len() as u64);
+                let mut block = AlcovBlock::new(
+                    u16::try_from(module_id)?,
+                    u16::try_from(segment_id)?,
+                    offset - module.segments[segment_id].module_range.start,
+                    *size,
+                    nb_taken,
+                );
+                block.nb_instructions = Some(*nb_instructions);
+                blocks.push(block);
+            }
+
+            modules.push(module.clone());
+        }
+
+        let edges = if self.edges {
+            let mut edges = AlcovEdges::new();
+            edges
+                .adj_list
+                .resize(blocks.len(), AlcovBlockEdges::default());
+
+            for ((src, dst), nb_taken) in &self.edges_taken {
+                let (Some(src_id), Some(dst_id)) = (block_ids.get(src), block_ids.get(dst)) else {
+                    continue;
+                };
+
+                edges.adj_list[*src_id as usize].dst_modules.insert(
+                    (*dst_id).into(),
+                    AlcovDstBlockEdgeMetadata {
+                        nb_taken: *nb_taken,
+                    },
+                );
+            }
+
+            Some(edges)
+        } else {
+            None
+        };
+
+        let mut alcov = Alcov::new(hdr, modules, blocks, edges);
+        for (tid, coverage) in &self.thread_coverage {
+            let mut thread = AlcovThread::new(tid.as_raw() as u64, coverage.name.clone());
+
+            for (block, nb_taken) in &coverage.nb_taken {
+                if let Some(&block_id) = block_ids.get(block) {
+                    thread.add_block(block_id, *nb_taken);
+                }
+            }
+
+            for ((src, dst), nb_taken) in &coverage.edges_taken {
+                if let (Some(&src_id), Some(&dst_id)) = (block_ids.get(src), block_ids.get(dst)) {
+                    thread.add_edge(src_id, dst_id, *nb_taken);
+                }
+            }
+
+            alcov.threads.push(thread);
+        }
+
+        Ok(alcov)
+    }
+}
@@ -0,0 +1,41 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovChecksum, Error};
+use alcov::{AlcovVersion, read_any};
+use clap_stdin::FileOrStdin;
+
+/// Convert an alcov file of an older version to the latest version of the format
+#[derive(Clone, Debug, Args)]
+pub struct Upgrade {
+    /// Output alcov file
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Input, or empty to get from STDIN.
+    #[arg(default_value = "-")]
+    input: FileOrStdin,
+}
+
+impl Upgrade {
+    pub fn run(self) -> Result<(), Error> {
+        let mut input_rdr = self.input.into_reader().unwrap();
+        let any = read_any(&mut input_rdr)?;
+
+        let version = any.version();
+        if version == AlcovVersion::LATEST {
+            eprintln!("already in the latest version ({}).", version);
+        }
+
+        let mut alcov = any.into_latest();
+        // files older than the integrity section get one.
+        if version < AlcovVersion::LATEST && alcov.hdr.checksum.is_none() {
+            alcov.hdr.checksum = Some(AlcovChecksum::Crc32c);
+        }
+
+        let mut writer = BufWriter::new(File::create(&self.output)?);
+        alcov.write(&mut writer)
+    }
+}
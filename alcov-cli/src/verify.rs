@@ -0,0 +1,43 @@
+use clap::Args;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process;
+
+#[cfg(feature = "v0")]
+use alcov::v0::{AlcovReader, Error};
+
+/// Check the checksums of alcov files, without decoding their blocks
+#[derive(Clone, Debug, Args)]
+pub struct Verify {
+    /// alcov files
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+}
+
+impl Verify {
+    pub fn run(self) -> Result<(), Error> {
+        let mut nb_failed = 0;
+
+        for path in &self.files {
+            let result = File::open(path)
+                .map_err(Error::from)
+                .and_then(|file| AlcovReader::verify(BufReader::new(file)));
+
+            match result {
+                Ok(Some(checksum)) => println!("{}: ok ({}).", path.display(), checksum),
+                Ok(None) => println!("{}: no checksums.", path.display()),
+                Err(err) => {
+                    println!("{}: failed ({:?}).", path.display(), err);
+                    nb_failed += 1;
+                }
+            }
+        }
+
+        if nb_failed > 0 {
+            process::exit(1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::v0;
+use crate::v0::{ED, Error};
+use byteorder::ReadBytesExt;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read};
+
+/// Size of the fields shared by every version: the magic, and the major and minor versions.
+const VERSION_SIZE: usize = 3 * size_of::<u64>();
+
+/// Version of an alcov file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AlcovVersion {
+    pub major: u64,
+    pub minor: u64,
+}
+
+/// alcov file of any version supported by the crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AnyAlcov {
+    V0(v0::Alcov),
+}
+
+impl AlcovVersion {
+    /// Version written by the crate.
+    pub const LATEST: Self = Self {
+        major: v0::bindings::ALCOV_VERSION_MAJOR,
+        minor: v0::bindings::ALCOV_VERSION_MINOR,
+    };
+
+    /// Read the magic and the version at the start of a file, which are the same across
+    /// every version.
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let magic = reader.read_u64::<ED>()?;
+        if magic != v0::bindings::ALCOV_MAGIC {
+            return Err(Error::WrongMagic);
+        }
+
+        let major = reader.read_u64::<ED>()?;
+        let minor = reader.read_u64::<ED>()?;
+
+        Ok(Self { major, minor })
+    }
+}
+
+impl AnyAlcov {
+    /// Version of the file it has been read from.
+    pub fn version(&self) -> AlcovVersion {
+        match self {
+            Self::V0(alcov) => AlcovVersion {
+                major: alcov.hdr.version_major,
+                minor: alcov.hdr.version_minor,
+            },
+        }
+    }
+
+    /// Convert to the latest version, as written by [`v0::Alcov::write`].
+    pub fn into_latest(self) -> v0::Alcov {
+        match self {
+            Self::V0(mut alcov) => {
+                alcov.hdr.version_major = AlcovVersion::LATEST.major;
+                alcov.hdr.version_minor = AlcovVersion::LATEST.minor;
+                alcov
+            }
+        }
+    }
+}
+
+/// Read an alcov file of any supported version, using its version to choose how to read
+/// it.
+pub fn read_any<R>(reader: &mut R) -> Result<AnyAlcov, Error>
+where
+    R: Read,
+{
+    let mut version_buf = [0u8; VERSION_SIZE];
+    reader.read_exact(&mut version_buf)?;
+    let version = AlcovVersion::read(&mut Cursor::new(&version_buf))?;
+
+    // the version fields are read again by the version module.
+    let mut reader = Cursor::new(version_buf).chain(reader);
+
+    match version.major {
+        0 => Ok(AnyAlcov::V0(v0::Alcov::read(&mut reader)?)),
+        _ => Err(Error::UnsupportedVersion {
+            major: version.major,
+            minor: version.minor,
+        }),
+    }
+}
+
+impl Display for AlcovVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}.{}", self.major, self.minor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{Alcov, AlcovBlock, AlcovHeader, AlcovModule, AlcovSegment};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_read_any() {
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap()];
+        let blocks = vec![AlcovBlock::new(0, 0, 0x10, 4, 1)];
+        let alcov = Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, false),
+            modules,
+            blocks,
+            None,
+        );
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+
+        let any = read_any(&mut Cursor::new(&out_buf)).unwrap();
+        assert_eq!(any.version(), AlcovVersion::LATEST);
+        assert_eq!(any.into_latest(), alcov);
+
+        // unknown minor version.
+        let mut unknown = out_buf.clone();
+        unknown[16..24].copy_from_slice(&(AlcovVersion::LATEST.minor + 1).to_le_bytes());
+        assert!(matches!(
+            read_any(&mut Cursor::new(&unknown)),
+            Err(Error::UnsupportedVersion { major: 0, .. })
+        ));
+
+        // unknown major version.
+        let mut unknown = out_buf.clone();
+        unknown[8..16].copy_from_slice(&1u64.to_le_bytes());
+        assert!(matches!(
+            read_any(&mut Cursor::new(&unknown)),
+            Err(Error::UnsupportedVersion { major: 1, .. })
+        ));
+
+        let mut wrong_magic = out_buf;
+        wrong_magic[0] ^= 1;
+        assert!(matches!(
+            read_any(&mut Cursor::new(&wrong_magic)),
+            Err(Error::WrongMagic)
+        ));
+    }
+}
@@ -2,3 +2,8 @@
 pub mod v0;
 #[cfg(feature = "v0")]
 pub use v0::*;
+
+#[cfg(feature = "v0")]
+pub mod any;
+#[cfg(feature = "v0")]
+pub use any::{AlcovVersion, AnyAlcov, read_any};
@@ -0,0 +1,265 @@
+use crate::v0::{Error, bindings};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+
+/// Instruction set family of a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlcovIsa {
+    X86,
+    /// ARM, or AArch64 for 64 bits.
+    Arm,
+    Mips,
+    PowerPc,
+    RiscV,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlcovEndianness {
+    Little,
+    Big,
+}
+
+/// Instruction encoding, for instruction sets with several of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AlcovIsaMode {
+    #[default]
+    Default,
+    Thumb,
+    Mips16,
+    MicroMips,
+}
+
+/// Architecture of a module, telling how to decode its instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlcovArch {
+    pub isa: AlcovIsa,
+    /// 16, 32 or 64.
+    pub bits: u8,
+    pub endianness: AlcovEndianness,
+    /// default mode of the module's code.
+    pub mode: AlcovIsaMode,
+}
+
+impl AlcovIsa {
+    pub const ALL: [AlcovIsa; 5] = [Self::X86, Self::Arm, Self::Mips, Self::PowerPc, Self::RiscV];
+
+    pub fn id(self) -> u8 {
+        let id = match self {
+            Self::X86 => bindings::ALCOV_ISA_X86,
+            Self::Arm => bindings::ALCOV_ISA_ARM,
+            Self::Mips => bindings::ALCOV_ISA_MIPS,
+            Self::PowerPc => bindings::ALCOV_ISA_POWERPC,
+            Self::RiscV => bindings::ALCOV_ISA_RISCV,
+        };
+
+        id as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|isa| isa.id() == id)
+            .ok_or(Error::WrongArch(id))
+    }
+}
+
+impl AlcovEndianness {
+    pub fn id(self) -> u8 {
+        let id = match self {
+            Self::Little => bindings::ALCOV_ENDIAN_LITTLE,
+            Self::Big => bindings::ALCOV_ENDIAN_BIG,
+        };
+
+        id as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, Error> {
+        [Self::Little, Self::Big]
+            .into_iter()
+            .find(|endianness| endianness.id() == id)
+            .ok_or(Error::WrongArch(id))
+    }
+}
+
+impl AlcovIsaMode {
+    pub const ALL: [AlcovIsaMode; 4] = [Self::Default, Self::Thumb, Self::Mips16, Self::MicroMips];
+
+    pub fn id(self) -> u8 {
+        let id = match self {
+            Self::Default => bindings::ALCOV_MODE_DEFAULT,
+            Self::Thumb => bindings::ALCOV_MODE_THUMB,
+            Self::Mips16 => bindings::ALCOV_MODE_MIPS16,
+            Self::MicroMips => bindings::ALCOV_MODE_MICROMIPS,
+        };
+
+        id as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.id() == id)
+            .ok_or(Error::WrongArch(id))
+    }
+}
+
+impl AlcovArch {
+    pub fn new(isa: AlcovIsa, bits: u8, endianness: AlcovEndianness) -> Self {
+        Self {
+            isa,
+            bits,
+            endianness,
+            mode: AlcovIsaMode::Default,
+        }
+    }
+
+    /// Architecture of an object file, if supported.
+    pub fn from_object<'data, O>(file: &O) -> Option<Self>
+    where
+        O: object::Object<'data>,
+    {
+        use object::Architecture;
+
+        let (isa, bits) = match file.architecture() {
+            Architecture::I386 => (AlcovIsa::X86, 32),
+            Architecture::X86_64 | Architecture::X86_64_X32 => (AlcovIsa::X86, 64),
+            Architecture::Arm => (AlcovIsa::Arm, 32),
+            Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => (AlcovIsa::Arm, 64),
+            Architecture::Mips => (AlcovIsa::Mips, 32),
+            Architecture::Mips64 => (AlcovIsa::Mips, 64),
+            Architecture::PowerPc => (AlcovIsa::PowerPc, 32),
+            Architecture::PowerPc64 => (AlcovIsa::PowerPc, 64),
+            Architecture::Riscv32 => (AlcovIsa::RiscV, 32),
+            Architecture::Riscv64 => (AlcovIsa::RiscV, 64),
+            _ => return None,
+        };
+
+        let endianness = if file.is_little_endian() {
+            AlcovEndianness::Little
+        } else {
+            AlcovEndianness::Big
+        };
+
+        Some(Self::new(isa, bits, endianness))
+    }
+
+    /// Write an architecture, or an unknown one.
+    pub fn write<W>(arch: Option<&Self>, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        match arch {
+            Some(arch) => {
+                writer.write_u8(arch.isa.id())?;
+                writer.write_u8(arch.bits)?;
+                writer.write_u8(arch.endianness.id())?;
+                writer.write_u8(arch.mode.id())?;
+            }
+            None => writer.write_all(&[bindings::ALCOV_ISA_UNKNOWN as u8, 0, 0, 0])?,
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Option<Self>, Error>
+    where
+        R: Read,
+    {
+        let isa = reader.read_u8()?;
+        let bits = reader.read_u8()?;
+        let endianness = reader.read_u8()?;
+        let mode = reader.read_u8()?;
+
+        if isa == bindings::ALCOV_ISA_UNKNOWN as u8 {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            isa: AlcovIsa::from_id(isa)?,
+            bits,
+            endianness: AlcovEndianness::from_id(endianness)?,
+            mode: AlcovIsaMode::from_id(mode)?,
+        }))
+    }
+}
+
+impl Display for AlcovIsa {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::X86 => "x86",
+            Self::Arm => "arm",
+            Self::Mips => "mips",
+            Self::PowerPc => "powerpc",
+            Self::RiscV => "riscv",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for AlcovIsaMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Default => "default",
+            Self::Thumb => "thumb",
+            Self::Mips16 => "mips16",
+            Self::MicroMips => "micromips",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for AlcovArch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let endianness = match self.endianness {
+            AlcovEndianness::Little => "little",
+            AlcovEndianness::Big => "big",
+        };
+
+        write!(f, "{} {}-bit {}-endian", self.isa, self.bits, endianness)?;
+        if self.mode != AlcovIsaMode::Default {
+            write!(f, " ({})", self.mode)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{Alcov, AlcovBlock, AlcovHeader, AlcovModule, AlcovPerms, AlcovSegment};
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_module_info() {
+        let mut segment = AlcovSegment::new(0x1000..0x2000);
+        segment.file_offset = Some(0x1000);
+        segment.perms = AlcovPerms::Read | AlcovPerms::Execute;
+
+        let mut modules = vec![
+            AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000), segment]).unwrap(),
+            AlcovModule::new(0x10000, None, vec![AlcovSegment::new(0..0x1000)]).unwrap(),
+        ];
+        let mut arch = AlcovArch::new(AlcovIsa::Arm, 32, AlcovEndianness::Little);
+        arch.mode = AlcovIsaMode::Thumb;
+        modules[0].arch = Some(arch);
+        assert_eq!(arch.to_string(), "arm 32-bit little-endian (thumb)");
+
+        let blocks = vec![AlcovBlock::new(0, 1, 0x10, 4, 1)];
+        let alcov = Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, false),
+            modules,
+            blocks,
+            None,
+        );
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::v0::{AlcovBlockEdges, AlcovBlockEdgesMetadata, ED, Error};
+use crate::v0::{
+    AlcovBlockEdges, AlcovBlockEdgesMetadata, AlcovIsaMode, AlcovModule, ED, Error, bindings,
+};
 use byteorder::{ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 
@@ -9,6 +11,10 @@ pub struct AlcovBlock {
     pub segment_offset: u64,
     pub size: u32,
     pub nb_taken: u64,
+    /// instruction encoding of the block, if it differs from the default one of its module.
+    pub mode: Option<AlcovIsaMode>,
+    /// number of instructions of the block, if known.
+    pub nb_instructions: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,9 +37,54 @@ impl AlcovBlock {
             segment_offset,
             size,
             nb_taken,
+            mode: None,
+            nb_instructions: None,
         }
     }
 
+    /// Instruction encoding of the block: its own if known, or else the default one of its
+    /// module.
+    pub fn isa_mode(&self, module: &AlcovModule) -> Option<AlcovIsaMode> {
+        self.mode.or(module.arch.map(|arch| arch.mode))
+    }
+
+    /// Whether the block has anything to write in the block info chunk.
+    pub fn has_info(&self) -> bool {
+        self.mode.is_some() || self.nb_instructions.is_some()
+    }
+
+    /// Write the entry of the block in the block info chunk.
+    pub fn write_info<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let mode = self
+            .mode
+            .map_or(bindings::ALCOV_MODE_UNKNOWN as u8, AlcovIsaMode::id);
+        writer.write_u8(mode)?;
+        writer.write_u32::<ED>(self.nb_instructions.unwrap_or(0))?;
+
+        Ok(())
+    }
+
+    /// Read the entry of the block in the block info chunk.
+    pub fn read_info<R>(&mut self, reader: &mut R) -> Result<(), Error>
+    where
+        R: Read,
+    {
+        let mode = reader.read_u8()?;
+        self.mode = if mode == bindings::ALCOV_MODE_UNKNOWN as u8 {
+            None
+        } else {
+            Some(AlcovIsaMode::from_id(mode)?)
+        };
+
+        let nb_instructions = reader.read_u32::<ED>()?;
+        self.nb_instructions = (nb_instructions != 0).then_some(nb_instructions);
+
+        Ok(())
+    }
+
     pub fn write<W>(
         &self,
         writer: &mut W,
@@ -77,6 +128,8 @@ impl AlcovBlock {
                 module_id,
                 segment_id,
                 nb_taken,
+                mode: None,
+                nb_instructions: None,
             },
             AlcovBlockMetadata {
                 nb_out_edges,
@@ -0,0 +1,155 @@
+use crate::v0::{ED, Error, bindings};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use xxhash_rust::xxh64::Xxh64;
+
+/// Algorithm used for the checksums of the integrity section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlcovChecksum {
+    Crc32c,
+    Xxh64,
+}
+
+/// Running checksum.
+#[derive(Clone)]
+pub enum AlcovHasher {
+    Crc32c(u32),
+    Xxh64(Xxh64),
+}
+
+/// Integrity section: checksums of every chunk, as stored in the file, and of the whole
+/// file except this section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlcovChecksums {
+    pub algorithm: AlcovChecksum,
+    pub file: u64,
+    /// checksum of each chunk, by chunk type.
+    pub chunks: Vec<(u16, u64)>,
+}
+
+impl AlcovChecksum {
+    pub const ALL: [AlcovChecksum; 2] = [Self::Crc32c, Self::Xxh64];
+
+    pub fn id(self) -> u8 {
+        let id = match self {
+            Self::Crc32c => bindings::ALCOV_CHECKSUM_CRC32C,
+            Self::Xxh64 => bindings::ALCOV_CHECKSUM_XXH64,
+        };
+
+        id as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.id() == id)
+            .ok_or(Error::WrongChecksumAlgorithm(id))
+    }
+
+    pub fn hasher(self) -> AlcovHasher {
+        match self {
+            Self::Crc32c => AlcovHasher::Crc32c(0),
+            Self::Xxh64 => AlcovHasher::Xxh64(Xxh64::new(0)),
+        }
+    }
+
+    pub fn checksum(self, data: &[u8]) -> u64 {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finish()
+    }
+}
+
+impl AlcovHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+            Self::Xxh64(xxh64) => xxh64.update(data),
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        match self {
+            Self::Crc32c(crc) => *crc as u64,
+            Self::Xxh64(xxh64) => xxh64.digest(),
+        }
+    }
+}
+
+impl AlcovChecksums {
+    /// Size of the section once written, for a number of chunks.
+    pub fn size(nb_chunks: usize) -> usize {
+        size_of::<bindings::alcov_checksums>()
+            + nb_chunks * size_of::<bindings::alcov_chunk_checksum>()
+    }
+
+    pub fn chunk(&self, chunk_type: u16) -> Option<u64> {
+        self.chunks
+            .iter()
+            .find(|(checksum_type, _)| *checksum_type == chunk_type)
+            .map(|(_, checksum)| *checksum)
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        writer.write_u8(self.algorithm.id())?;
+        writer.write_u64::<ED>(self.file)?;
+        writer.write_u16::<ED>(u16::try_from(self.chunks.len())?)?;
+
+        for (chunk_type, checksum) in &self.chunks {
+            writer.write_u16::<ED>(*chunk_type)?;
+            writer.write_u64::<ED>(*checksum)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let algorithm = AlcovChecksum::from_id(reader.read_u8()?)?;
+        let file = reader.read_u64::<ED>()?;
+        let nb_chunks = reader.read_u16::<ED>()?;
+
+        let mut chunks: Vec<(u16, u64)> = Vec::with_capacity(nb_chunks as usize);
+        for _ in 0..nb_chunks {
+            let chunk_type = reader.read_u16::<ED>()?;
+            let checksum = reader.read_u64::<ED>()?;
+            chunks.push((chunk_type, checksum));
+        }
+
+        Ok(Self {
+            algorithm,
+            file,
+            chunks,
+        })
+    }
+}
+
+impl Display for AlcovChecksum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Crc32c => "crc32c",
+            Self::Xxh64 => "xxh64",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AlcovChecksum {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.to_string() == s)
+            .ok_or_else(|| format!("unknown checksum algorithm {s}"))
+    }
+}
@@ -0,0 +1,136 @@
+use crate::v0::{Alcov, AlcovBlock, AlcovModule, ED, Error};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Cursor, Read, Write};
+
+impl AlcovModule {
+    /// Keep a snapshot of the code of the module, for modules whose bytes cannot be found
+    /// once the process exits (e.g. JIT regions or unpacked code).
+    ///
+    /// `memory` holds the bytes mapped from the base address of the module. Bytes past
+    /// the end of its last segment are ignored.
+    pub fn capture_code(&mut self, memory: &[u8]) {
+        let end = self
+            .segments
+            .iter()
+            .map(|segment| segment.module_range.end)
+            .max()
+            .unwrap_or(0);
+        let end = usize::try_from(end).map_or(memory.len(), |end| end.min(memory.len()));
+
+        self.code = Some(memory[..end].to_vec());
+    }
+
+    /// Bytes of the code snapshot at an offset from the module base address, if captured.
+    pub fn code_at(&self, module_offset: u64, size: u64) -> Option<&[u8]> {
+        let start = usize::try_from(module_offset).ok()?;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+
+        self.code.as_ref()?.get(start..end)
+    }
+}
+
+impl Alcov {
+    /// Bytes of a block, if the code of its module has been captured.
+    pub fn block_code(&self, block: &AlcovBlock) -> Option<&[u8]> {
+        let module = self.modules.get(block.module_id as usize)?;
+        let offset = module.module_offset(block.segment_id, block.segment_offset)?;
+
+        module.code_at(offset, block.size as u64)
+    }
+}
+
+/// Write the module code chunk, with the code snapshot of every module having one.
+pub fn write_code<W>(writer: &mut W, modules: &[AlcovModule]) -> Result<(), Error>
+where
+    W: Write,
+{
+    for (module_id, module) in modules.iter().enumerate() {
+        if let Some(code) = &module.code {
+            writer.write_u16::<ED>(u16::try_from(module_id)?)?;
+            writer.write_u64::<ED>(code.len() as u64)?;
+            writer.write_all(code)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the module code chunk, adding the code snapshots to their module.
+pub fn read_code(code_buf: &[u8], modules: &mut [AlcovModule]) -> Result<(), Error> {
+    let mut code_rdr = Cursor::new(code_buf);
+
+    while code_rdr.position() < code_buf.len() as u64 {
+        let module_id = code_rdr.read_u16::<ED>()?;
+        let size = code_rdr.read_u64::<ED>()?;
+
+        // the size is checked before allocating, as it may be corrupted.
+        if size > code_buf.len() as u64 - code_rdr.position() {
+            return Err(Error::MalformedModuleCode);
+        }
+
+        let mut code = vec![0u8; usize::try_from(size)?];
+        code_rdr.read_exact(&mut code)?;
+
+        modules
+            .get_mut(module_id as usize)
+            .ok_or(Error::UnknownModule { module_id })?
+            .code = Some(code);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{AlcovCodec, AlcovHeader, AlcovSegment};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_module_code() {
+        let mut modules = vec![
+            AlcovModule::new(
+                0x400000,
+                Some(PathBuf::from("/bin/a")),
+                vec![AlcovSegment::new(0..0x1000)],
+            )
+            .unwrap(),
+            AlcovModule::new(
+                0x7f0000,
+                None,
+                vec![AlcovSegment::new(0..0x10), AlcovSegment::new(0x20..0x30)],
+            )
+            .unwrap(),
+        ];
+        let memory: Vec<u8> = (0..0x40).collect();
+        modules[1].capture_code(&memory);
+        assert_eq!(modules[1].code.as_ref().unwrap().len(), 0x30);
+
+        let blocks = vec![
+            AlcovBlock::new(0, 0, 0x10, 4, 1),
+            AlcovBlock::new(1, 1, 0x4, 3, 1),
+        ];
+        let mut hdr = AlcovHeader::new(None::<PathBuf>, false);
+        hdr.set_codec(AlcovCodec::Zstd);
+        let alcov = Alcov::new(hdr, modules, blocks, None);
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        let alcov = Alcov::read(&mut Cursor::new(out_buf)).unwrap();
+
+        assert_eq!(alcov.block_code(&alcov.blocks[0]), None);
+        assert_eq!(
+            alcov.block_code(&alcov.blocks[1]),
+            Some(&[0x24, 0x25, 0x26][..])
+        );
+        assert_eq!(alcov.modules[1].code_at(0x2e, 4), None);
+
+        let mut code_buf: Vec<u8> = Vec::new();
+        write_code(&mut code_buf, &alcov.modules).unwrap();
+        code_buf.truncate(code_buf.len() - 1);
+        assert!(matches!(
+            read_code(&code_buf, &mut alcov.modules.clone()),
+            Err(Error::MalformedModuleCode)
+        ));
+    }
+}
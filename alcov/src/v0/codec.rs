@@ -0,0 +1,157 @@
+use crate::v0::{Error, bindings};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read};
+use std::str::FromStr;
+
+/// Level used for zstd compression.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Initial capacity of a decompressed chunk, relative to its compressed size. Buffers
+/// grow as needed, so that a corrupted raw size cannot trigger a huge allocation.
+const INITIAL_RATIO: usize = 16;
+
+/// lz4 blocks cannot expand data more than this.
+const LZ4_MAX_RATIO: usize = 255;
+
+/// Codec used to compress a chunk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AlcovCodec {
+    #[default]
+    None,
+    Lzma2,
+    Zstd,
+    Lz4,
+}
+
+impl AlcovCodec {
+    pub const ALL: [AlcovCodec; 4] = [Self::None, Self::Lzma2, Self::Zstd, Self::Lz4];
+
+    pub fn id(self) -> u8 {
+        let id = match self {
+            Self::None => bindings::ALCOV_CODEC_NONE,
+            Self::Lzma2 => bindings::ALCOV_CODEC_LZMA2,
+            Self::Zstd => bindings::ALCOV_CODEC_ZSTD,
+            Self::Lz4 => bindings::ALCOV_CODEC_LZ4,
+        };
+
+        id as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, Error> {
+        Self::ALL
+            .into_iter()
+            .find(|codec| codec.id() == id)
+            .ok_or(Error::WrongCodec(id))
+    }
+
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let compressed = match self {
+            Self::None => data.to_vec(),
+            Self::Lzma2 => {
+                let mut compressed = Vec::new();
+                lzma_rs::lzma2_compress(&mut Cursor::new(data), &mut compressed)?;
+                compressed
+            }
+            Self::Zstd => zstd::bulk::compress(data, ZSTD_LEVEL)?,
+            Self::Lz4 => lz4_flex::block::compress(data),
+        };
+
+        Ok(compressed)
+    }
+
+    /// Decompress a chunk, checking its size once decompressed.
+    pub fn decompress(self, data: Vec<u8>, raw_size: u64) -> Result<Vec<u8>, Error> {
+        let raw_size = usize::try_from(raw_size)?;
+        let capacity = raw_size.min(data.len().saturating_mul(INITIAL_RATIO));
+
+        let raw = match self {
+            Self::None => data,
+            Self::Lzma2 => {
+                let mut raw = Vec::with_capacity(capacity);
+                lzma_rs::lzma2_decompress(&mut Cursor::new(data), &mut raw)?;
+                raw
+            }
+            Self::Zstd => {
+                // one more byte than expected is enough to detect a wrong raw size.
+                let mut raw = Vec::with_capacity(capacity);
+                zstd::stream::read::Decoder::new(&*data)?
+                    .take(raw_size as u64 + 1)
+                    .read_to_end(&mut raw)?;
+                raw
+            }
+            Self::Lz4 => lz4_flex::block::decompress(
+                &data,
+                raw_size.min(data.len().saturating_mul(LZ4_MAX_RATIO)),
+            )?,
+        };
+
+        if raw.len() != raw_size {
+            return Err(Error::WrongRawSize {
+                expected: raw_size as u64,
+                found: raw.len() as u64,
+            });
+        }
+
+        Ok(raw)
+    }
+}
+
+impl Display for AlcovCodec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::None => "none",
+            Self::Lzma2 => "lzma2",
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AlcovCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|codec| codec.to_string() == s)
+            .ok_or_else(|| format!("unknown codec {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let data: Vec<u8> = (0..10000u32).flat_map(|i| (i % 97).to_le_bytes()).collect();
+
+        for codec in AlcovCodec::ALL {
+            let compressed = codec.compress(&data).unwrap();
+            let raw = codec.decompress(compressed, data.len() as u64).unwrap();
+            assert_eq!(raw, data, "{codec}");
+        }
+
+        let compressed = AlcovCodec::Zstd.compress(&data).unwrap();
+        assert!(
+            AlcovCodec::Zstd
+                .decompress(compressed, data.len() as u64 - 1)
+                .is_err()
+        );
+
+        // a corrupted raw size is only found out once decompressed.
+        for codec in AlcovCodec::ALL {
+            let compressed = codec.compress(&data).unwrap();
+            assert!(matches!(
+                codec.decompress(compressed, 1 << 50),
+                Err(Error::WrongRawSize {
+                    expected: 0x4000000000000,
+                    ..
+                })
+            ));
+        }
+    }
+}
@@ -70,7 +70,7 @@ impl AlcovEdges {
     /// particular edge.
     /// 
     /// If either src_block or dst_block is invalid, an error is returned.
-    pub fn add(&mut self, blocks: &Vec<AlcovBlock>, src_block: u64, dst_block: u64) -> Result<(), Error> {
+    pub fn add(&mut self, blocks: &[AlcovBlock], src_block: u64, dst_block: u64) -> Result<(), Error> {
         if src_block as usize >= blocks.len() {
             Err(Error::EdgeWithoutBlock {
                 block_id: src_block
@@ -0,0 +1,231 @@
+use crate::v0::{AlcovArch, AlcovModule, AlcovModuleIdentity, AlcovPerms, AlcovSegment, Error};
+use object::{Object, ObjectKind, ObjectSegment, SegmentFlags, elf};
+use std::fs;
+use std::path::PathBuf;
+
+const PAGE_SIZE: u64 = 0x1000;
+
+impl AlcovModule {
+    /// Build a module from the `PT_LOAD` program headers of an ELF file.
+    ///
+    /// `base_address` is the address at which the lowest loadable page of the file is
+    /// mapped, i.e. the start of its first mapping in `/proc/<pid>/maps`. Segments are
+    /// page-aligned and relative to this address, as they are mapped by the loader: their
+    /// `p_align` can be larger than a page.
+    ///
+    /// Non-PIE executables are always mapped at their link address, so `base_address` is
+    /// ignored for them. Prelinked libraries have a non-zero link address, which is
+    /// handled the same way as a PIE linked at 0.
+    ///
+    /// The identities and architecture of the module, and the file offsets and permissions
+    /// of its segments, are taken from the file.
+    pub fn from_elf<P>(path: P, base_address: u64) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let data = fs::read(&path)?;
+
+        Self::from_elf_data(path, &data, base_address)
+    }
+
+    /// Build a module from the content of an ELF file already read from `path`.
+    ///
+    /// See [`AlcovModule::from_elf`].
+    pub fn from_elf_data<P>(path: P, data: &[u8], base_address: u64) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let file = object::File::parse(data).map_err(|_| Error::MalformedBinary)?;
+        let layout = elf_layout(&file)?;
+
+        let base_address = if layout.kind == ObjectKind::Executable {
+            layout.image_base
+        } else {
+            base_address
+        };
+
+        let mut module = Self::new(base_address, Some(path.into()), layout.segments)?;
+        module.identities = AlcovModuleIdentity::from_file_data(data);
+        module.arch = layout.arch;
+
+        Ok(module)
+    }
+
+    /// Link address of the lowest loadable page of an ELF file.
+    pub fn elf_image_base(file: &object::File) -> Result<u64, Error> {
+        Ok(elf_layout(file)?.image_base)
+    }
+}
+
+/// Loadable layout of an ELF file.
+struct ElfLayout {
+    kind: ObjectKind,
+    image_base: u64,
+    segments: Vec<AlcovSegment>,
+    arch: Option<AlcovArch>,
+}
+
+fn elf_layout(file: &object::File) -> Result<ElfLayout, Error> {
+    if file.format() != object::BinaryFormat::Elf {
+        return Err(Error::MalformedBinary);
+    }
+
+    let segments: Vec<AlcovSegment> = file
+        .segments()
+        .filter(|segment| segment.size() > 0)
+        .map(|segment| {
+            let start = segment.address() & !(PAGE_SIZE - 1);
+            let end = (segment.address() + segment.size()).next_multiple_of(PAGE_SIZE);
+
+            let mut perms = AlcovPerms::empty();
+            if let SegmentFlags::Elf { p_flags } = segment.flags() {
+                perms.set(AlcovPerms::Read, p_flags & elf::PF_R != 0);
+                perms.set(AlcovPerms::Write, p_flags & elf::PF_W != 0);
+                perms.set(AlcovPerms::Execute, p_flags & elf::PF_X != 0);
+            }
+
+            AlcovSegment {
+                module_range: start..end,
+                file_offset: Some(segment.file_range().0 & !(PAGE_SIZE - 1)),
+                perms,
+            }
+        })
+        .collect();
+
+    let image_base = segments
+        .iter()
+        .map(|segment| segment.module_range.start)
+        .min()
+        .ok_or(Error::EmptyModule)?;
+
+    let segments = segments
+        .into_iter()
+        .map(|mut segment| {
+            segment.module_range =
+                (segment.module_range.start - image_base)..(segment.module_range.end - image_base);
+            segment
+        })
+        .collect();
+
+    Ok(ElfLayout {
+        kind: file.kind(),
+        image_base,
+        segments,
+        arch: AlcovArch::from_object(file),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// A minimal little-endian ELF64 x86-64 file, with only program headers given as
+    /// `(p_flags, p_offset, p_vaddr, p_memsz, p_align)`.
+    fn elf_fixture(e_type: u16, phdrs: &[(u32, u64, u64, u64, u64)]) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(b"\x7fELF\x02\x01\x01");
+        data.resize(16, 0);
+        data.extend_from_slice(&e_type.to_le_bytes());
+        data.extend_from_slice(&elf::EM_X86_64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+        data.extend_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data.extend_from_slice(&0u64.to_le_bytes()); // e_shoff
+        data.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        data.extend_from_slice(&64u16.to_le_bytes()); // e_ehsize
+        data.extend_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        data.extend_from_slice(&(phdrs.len() as u16).to_le_bytes());
+        data.extend_from_slice(&[0; 6]); // e_shentsize, e_shnum, e_shstrndx
+
+        for &(p_flags, p_offset, p_vaddr, p_memsz, p_align) in phdrs {
+            data.extend_from_slice(&elf::PT_LOAD.to_le_bytes());
+            data.extend_from_slice(&p_flags.to_le_bytes());
+            data.extend_from_slice(&p_offset.to_le_bytes());
+            data.extend_from_slice(&p_vaddr.to_le_bytes());
+            data.extend_from_slice(&p_vaddr.to_le_bytes()); // p_paddr
+            data.extend_from_slice(&0u64.to_le_bytes()); // p_filesz
+            data.extend_from_slice(&p_memsz.to_le_bytes());
+            data.extend_from_slice(&p_align.to_le_bytes());
+        }
+
+        data
+    }
+
+    fn layout(module: &AlcovModule) -> Vec<(std::ops::Range<u64>, Option<u64>, AlcovPerms)> {
+        module
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.module_range.clone(),
+                    segment.file_offset,
+                    segment.perms,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_elf() {
+        // segments are page-aligned, whatever their `p_align`.
+        let dyn_data = elf_fixture(
+            elf::ET_DYN,
+            &[
+                (elf::PF_R, 0, 0, 0x5e8, 0x10000),
+                (elf::PF_R | elf::PF_X, 0x1000, 0x1000, 0x1a5, 0x10000),
+                (elf::PF_R | elf::PF_W, 0x2df0, 0x12df0, 0x228, 0x10000),
+            ],
+        );
+        let path = std::env::temp_dir().join(format!("alcov-test-{}.so", std::process::id()));
+        fs::write(&path, &dyn_data).unwrap();
+        let module = AlcovModule::from_elf(&path, 0x7f0000000000);
+        fs::remove_file(&path).unwrap();
+        let module = module.unwrap();
+
+        assert_eq!(module.base_address, 0x7f0000000000);
+        assert_eq!(module.path.as_deref(), Some(path.as_path()));
+        assert_eq!(
+            module.arch,
+            AlcovArch::from_object(&object::File::parse(&*dyn_data).unwrap())
+        );
+        assert_eq!(
+            layout(&module),
+            vec![
+                (0..0x1000, Some(0), AlcovPerms::Read),
+                (
+                    0x1000..0x2000,
+                    Some(0x1000),
+                    AlcovPerms::Read | AlcovPerms::Execute
+                ),
+                (
+                    0x12000..0x14000,
+                    Some(0x2000),
+                    AlcovPerms::Read | AlcovPerms::Write
+                ),
+            ]
+        );
+
+        let exec_data = elf_fixture(
+            elf::ET_EXEC,
+            &[
+                (elf::PF_R, 0, 0x400000, 0x5e8, 0x200000),
+                (elf::PF_R | elf::PF_X, 0x1000, 0x401000, 0x1a5, 0x200000),
+            ],
+        );
+        let module =
+            AlcovModule::from_elf_data(Path::new("/bin/a"), &exec_data, 0x55d0c4a00000).unwrap();
+        assert_eq!(module.base_address, 0x400000);
+        assert_eq!(module.segments[1].module_range, 0x1000..0x2000);
+        assert_eq!(
+            AlcovModule::elf_image_base(&object::File::parse(&*exec_data).unwrap()).unwrap(),
+            0x400000
+        );
+
+        assert!(matches!(
+            AlcovModule::from_elf_data(Path::new("/bin/b"), b"\x7fELF", 0),
+            Err(Error::MalformedBinary)
+        ));
+    }
+}
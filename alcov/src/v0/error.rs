@@ -1,6 +1,7 @@
 use std::ffi::FromBytesUntilNulError;
 use std::io;
 use std::num::TryFromIntError;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum Error {
@@ -12,10 +13,59 @@ pub enum Error {
     MalformedBinary,
     PathReadError(FromBytesUntilNulError),
     PathEncodingError,
+    WrongPathOffset(u64),
     DecompressError(lzma_rs::error::Error),
     EdgeWithoutBlock{
         block_id: u64,
     },
+    MalformedMaps {
+        line: usize,
+    },
+    UnknownModule {
+        module_id: u16,
+    },
+    BlockOutOfModule {
+        block_id: u64,
+    },
+    MissingSymbol(String),
+    MalformedCallgrind {
+        line: usize,
+    },
+    MalformedModOff {
+        line: usize,
+    },
+    ModuleNotFound(PathBuf),
+    WrongCodec(u8),
+    Lz4DecompressError(lz4_flex::block::DecompressError),
+    WrongRawSize {
+        expected: u64,
+        found: u64,
+    },
+    MissingChunk(u16),
+    MisplacedChunk(u16),
+    MalformedFrames,
+    EdgesAfterSeek,
+    WrongChecksumAlgorithm(u8),
+    ChunkChecksumMismatch(u16),
+    FileChecksumMismatch,
+    UnsupportedVersion {
+        major: u64,
+        minor: u64,
+    },
+    WrongModuleIdentity(u8),
+    MalformedModuleIdentity,
+    ModuleIdentityMismatch(PathBuf),
+    WrongArch(u8),
+    WrongMetadataType(u8),
+    MalformedMetadata,
+    InputHashMismatch,
+    MalformedModuleCode,
+    MalformedThreads,
+    UnknownThread(u64),
+    ThreadWithoutBlock {
+        tid: u64,
+        block_id: u64,
+    },
 }
 
 impl From<io::Error> for Error {
@@ -41,3 +91,9 @@ impl From<lzma_rs::error::Error> for Error {
         Error::DecompressError(err)
     }
 }
+
+impl From<lz4_flex::block::DecompressError> for Error {
+    fn from(err: lz4_flex::block::DecompressError) -> Self {
+        Error::Lz4DecompressError(err)
+    }
+}
@@ -0,0 +1,157 @@
+use crate::v0::{AlcovCodec, ED, Error, bindings};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+use std::thread;
+
+/// Number of blocks per frame used by [`crate::v0::Alcov::write`].
+pub const BLOCKS_PER_FRAME: u64 = 1 << 16;
+
+const BLOCK_SIZE: u64 = size_of::<bindings::alcov_block>() as u64;
+
+/// Frame of the blocks chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlcovFrame {
+    /// offset from the start of the blocks chunk.
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Index of the independently compressed frames of the blocks chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlcovBlockFrames {
+    pub nb_blocks_per_frame: u64,
+    pub frames: Vec<AlcovFrame>,
+}
+
+/// Apply `f` to every item on all available cores, keeping the order of the items.
+fn par_map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    let nb_threads = thread::available_parallelism().map_or(1, |nb| nb.get());
+    if nb_threads == 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(nb_threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(&f).collect::<Vec<U>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+impl AlcovBlockFrames {
+    /// Split raw blocks in frames and compress them, returning the index and the frames.
+    pub fn compress(
+        blocks_buf: &[u8],
+        codec: AlcovCodec,
+        nb_blocks_per_frame: u64,
+    ) -> Result<(Self, Vec<u8>), Error> {
+        let frame_len = usize::try_from(nb_blocks_per_frame * BLOCK_SIZE)?;
+        let raw_frames: Vec<&[u8]> = blocks_buf.chunks(frame_len).collect();
+
+        let mut frames: Vec<AlcovFrame> = Vec::with_capacity(raw_frames.len());
+        let mut frames_buf: Vec<u8> = Vec::new();
+        for data in par_map(&raw_frames, |raw| codec.compress(raw)) {
+            let data = data?;
+
+            frames.push(AlcovFrame {
+                offset: frames_buf.len() as u64,
+                size: data.len() as u64,
+            });
+            frames_buf.extend(data);
+        }
+
+        Ok((
+            Self {
+                nb_blocks_per_frame,
+                frames,
+            },
+            frames_buf,
+        ))
+    }
+
+    /// Decompress frames in parallel, given as their ID and compressed data.
+    pub fn decompress(
+        &self,
+        frames: &[(usize, &[u8])],
+        codec: AlcovCodec,
+        nb_blocks: u64,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        par_map(frames, |&(frame_id, data)| {
+            codec.decompress(data.to_vec(), self.raw_size(frame_id, nb_blocks)?)
+        })
+        .into_iter()
+        .collect()
+    }
+
+    /// Frame holding a block.
+    pub fn frame_of(&self, block_id: u64) -> usize {
+        (block_id / self.nb_blocks_per_frame) as usize
+    }
+
+    /// Size of a frame once decompressed.
+    pub fn raw_size(&self, frame_id: usize, nb_blocks: u64) -> Result<u64, Error> {
+        let first_block = (frame_id as u64).saturating_mul(self.nb_blocks_per_frame);
+        let nb_frame_blocks = nb_blocks
+            .saturating_sub(first_block)
+            .min(self.nb_blocks_per_frame);
+
+        nb_frame_blocks
+            .checked_mul(BLOCK_SIZE)
+            .ok_or(Error::MalformedFrames)
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        writer.write_u64::<ED>(self.nb_blocks_per_frame)?;
+        writer.write_u64::<ED>(self.frames.len() as u64)?;
+
+        for frame in &self.frames {
+            writer.write_u64::<ED>(frame.offset)?;
+            writer.write_u64::<ED>(frame.size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the index of the frames holding `nb_blocks` blocks.
+    pub fn read<R>(reader: &mut R, nb_blocks: u64) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let nb_blocks_per_frame = reader.read_u64::<ED>()?;
+        let nb_frames = reader.read_u64::<ED>()?;
+
+        // frames must cover the blocks, and their size must fit in memory.
+        if nb_blocks_per_frame == 0
+            || nb_frames != nb_blocks.div_ceil(nb_blocks_per_frame)
+            || nb_blocks_per_frame.checked_mul(BLOCK_SIZE).is_none()
+        {
+            return Err(Error::MalformedFrames);
+        }
+
+        let mut frames: Vec<AlcovFrame> = Vec::new();
+        for _ in 0..nb_frames {
+            let offset = reader.read_u64::<ED>()?;
+            let size = reader.read_u64::<ED>()?;
+            frames.push(AlcovFrame { offset, size });
+        }
+
+        Ok(Self {
+            nb_blocks_per_frame,
+            frames,
+        })
+    }
+}
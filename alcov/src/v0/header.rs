@@ -1,7 +1,8 @@
-use crate::v0::{ED, Error, bindings};
+use crate::v0::{AlcovChecksum, AlcovCodec, AlcovInput, AlcovValue, ED, Error, bindings};
 use bitflags::bitflags;
-use byteorder::ReadBytesExt;
+use byteorder::{ReadBytesExt, WriteBytesExt};
 use std::fmt::{Display, Formatter};
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::{fmt, iter};
@@ -12,9 +13,21 @@ bitflags! {
         const Edges = bindings::ALCOV_FLAG_EDGES as u16;
         const Compress = bindings::ALCOV_FLAG_COMPRESS as u16;
         const InputPath = bindings::ALCOV_FLAG_INPUT_PATH as u16;
+        const RawPaths = bindings::ALCOV_FLAG_RAW_PATHS as u16;
     }
 }
 
+/// Entry of the chunk table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlcovChunk {
+    /// one of `bindings::ALCOV_CHUNK_*`. unknown types are kept as is.
+    pub chunk_type: u16,
+    pub codec: AlcovCodec,
+    pub offset: u64,
+    pub size: u64,
+    pub raw_size: u64,
+}
+
 /// helper for parsing
 #[derive(Debug, Clone)]
 pub struct AlcovHeaderMetadata {
@@ -23,19 +36,25 @@ pub struct AlcovHeaderMetadata {
     pub nb_modules: u16,
     pub nb_blocks: u64,
     pub nb_edges: u64,
-    pub modules_start: u64,
-    pub paths_start: u64,
-    pub blocks_start: u64,
-    pub edges_start: u64,
     pub flags: AlcovFlags,
+    pub chunks: Vec<AlcovChunk>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AlcovHeader {
     pub version_major: u64,
     pub version_minor: u64,
-    pub compress: bool,
+    /// codec of the blocks chunk.
+    pub blocks_codec: AlcovCodec,
+    /// codec of the edges chunk, if any.
+    pub edges_codec: AlcovCodec,
+    /// algorithm of the integrity section, if any.
+    pub checksum: Option<AlcovChecksum>,
     pub input_path: Option<PathBuf>,
+    /// content of the input, if embedded in the trace.
+    pub input: Option<AlcovInput>,
+    /// free-form metadata, such as [`crate::v0::metadata::keys`].
+    pub metadata: BTreeMap<String, AlcovValue>,
 }
 
 impl Display for AlcovFlags {
@@ -49,7 +68,52 @@ impl Display for AlcovFlags {
     }
 }
 
+impl AlcovChunk {
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        writer.write_u16::<ED>(self.chunk_type)?;
+        writer.write_u8(self.codec.id())?;
+        writer.write_u64::<ED>(self.offset)?;
+        writer.write_u64::<ED>(self.size)?;
+        writer.write_u64::<ED>(self.raw_size)?;
+
+        Ok(())
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let chunk_type = reader.read_u16::<ED>()?;
+        let codec = AlcovCodec::from_id(reader.read_u8()?)?;
+        let offset = reader.read_u64::<ED>()?;
+        let size = reader.read_u64::<ED>()?;
+        let raw_size = reader.read_u64::<ED>()?;
+
+        Ok(Self {
+            chunk_type,
+            codec,
+            offset,
+            size,
+            raw_size,
+        })
+    }
+}
+
 impl AlcovHeaderMetadata {
+    /// Size of the header once written, chunk table included.
+    pub fn size(nb_chunks: usize) -> u64 {
+        (size_of::<bindings::alcov_hdr>() + nb_chunks * size_of::<bindings::alcov_chunk>()) as u64
+    }
+
+    pub fn chunk(&self, chunk_type: u16) -> Option<&AlcovChunk> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type == chunk_type)
+    }
+
     pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
     where
         W: Write,
@@ -60,11 +124,12 @@ impl AlcovHeaderMetadata {
         writer.write_all(&self.nb_modules.to_le_bytes())?;
         writer.write_all(&self.nb_blocks.to_le_bytes())?;
         writer.write_all(&self.nb_edges.to_le_bytes())?;
-        writer.write_all(&self.modules_start.to_le_bytes())?;
-        writer.write_all(&self.paths_start.to_le_bytes())?;
-        writer.write_all(&self.blocks_start.to_le_bytes())?;
-        writer.write_all(&self.edges_start.to_le_bytes())?;
         writer.write_all(&self.flags.bits().to_le_bytes())?;
+        writer.write_all(&u16::try_from(self.chunks.len())?.to_le_bytes())?;
+
+        for chunk in &self.chunks {
+            chunk.write(writer)?;
+        }
 
         Ok(())
     }
@@ -75,12 +140,67 @@ impl AlcovHeaderMetadata {
     {
         let magic = reader.read_u64::<ED>()?;
 
-        if !magic == bindings::ALCOV_MAGIC {
+        if magic != bindings::ALCOV_MAGIC {
             return Err(Error::WrongMagic);
         }
 
         let version_major = reader.read_u64::<ED>()?;
         let version_minor = reader.read_u64::<ED>()?;
+
+        // every minor version since v0.1 can be read.
+        if version_major != bindings::ALCOV_VERSION_MAJOR
+            || !(1..=bindings::ALCOV_VERSION_MINOR).contains(&version_minor)
+        {
+            return Err(Error::UnsupportedVersion {
+                major: version_major,
+                minor: version_minor,
+            });
+        }
+
+        if version_minor == 1 {
+            return Self::read_v0_1(reader, version_major, version_minor);
+        }
+
+        let nb_modules = reader.read_u16::<ED>()?;
+        let nb_blocks = reader.read_u64::<ED>()?;
+        let nb_edges = reader.read_u64::<ED>()?;
+
+        let flags_int: u16 = reader.read_u16::<ED>()?;
+        let flags = AlcovFlags::from_bits(flags_int).ok_or(Error::WrongFlags(flags_int))?;
+
+        let nb_chunks = reader.read_u16::<ED>()?;
+        let mut chunks: Vec<AlcovChunk> = Vec::with_capacity(nb_chunks as usize);
+        for _ in 0..nb_chunks {
+            let chunk = AlcovChunk::read(reader)?;
+
+            // chunks must be sorted to be read sequentially.
+            let end = chunks
+                .last()
+                .map_or(Self::size(nb_chunks as usize), |prev| prev.offset + prev.size);
+            if chunk.offset < end {
+                return Err(Error::MisplacedChunk(chunk.chunk_type));
+            }
+
+            chunks.push(chunk);
+        }
+
+        Ok(AlcovHeaderMetadata {
+            version_major,
+            version_minor,
+            nb_modules,
+            nb_blocks,
+            nb_edges,
+            flags,
+            chunks,
+        })
+    }
+
+    /// Read the rest of a v0.1 header, where chunks are given by their start offset and
+    /// follow each other until the end of the file.
+    fn read_v0_1<R>(reader: &mut R, version_major: u64, version_minor: u64) -> Result<Self, Error>
+    where
+        R: Read,
+    {
         let nb_modules = reader.read_u16::<ED>()?;
         let nb_blocks = reader.read_u64::<ED>()?;
         let nb_edges = reader.read_u64::<ED>()?;
@@ -92,31 +212,109 @@ impl AlcovHeaderMetadata {
         let flags_int: u16 = reader.read_u16::<ED>()?;
         let flags = AlcovFlags::from_bits(flags_int).ok_or(Error::WrongFlags(flags_int))?;
 
+        let codec = if flags.intersects(AlcovFlags::Compress) {
+            AlcovCodec::Lzma2
+        } else {
+            AlcovCodec::None
+        };
+        // offsets may be unsorted in a corrupted header.
+        let raw_chunk = |chunk_type: u32, start: u64, end: u64| {
+            let size = end
+                .checked_sub(start)
+                .ok_or(Error::MisplacedChunk(chunk_type as u16))?;
+
+            Ok::<_, Error>(AlcovChunk {
+                chunk_type: chunk_type as u16,
+                codec: AlcovCodec::None,
+                offset: start,
+                size,
+                raw_size: size,
+            })
+        };
+
+        let mut chunks = vec![
+            raw_chunk(bindings::ALCOV_CHUNK_MODULES, modules_start, paths_start)?,
+            raw_chunk(bindings::ALCOV_CHUNK_PATHS, paths_start, blocks_start)?,
+        ];
+
+        // the size of the last chunk is unknown: it is read until the end of the file.
+        let blocks_raw_size = nb_blocks * size_of::<bindings::alcov_block>() as u64;
+        let blocks_end = if flags.intersects(AlcovFlags::Edges) {
+            edges_start
+        } else {
+            u64::MAX
+        };
+        chunks.push(AlcovChunk {
+            chunk_type: bindings::ALCOV_CHUNK_BLOCKS as u16,
+            codec,
+            offset: blocks_start,
+            size: blocks_end
+                .checked_sub(blocks_start)
+                .ok_or(Error::MisplacedChunk(bindings::ALCOV_CHUNK_BLOCKS as u16))?,
+            raw_size: blocks_raw_size,
+        });
+
+        if flags.intersects(AlcovFlags::Edges) {
+            chunks.push(AlcovChunk {
+                chunk_type: bindings::ALCOV_CHUNK_EDGES as u16,
+                codec,
+                offset: edges_start,
+                size: u64::MAX - edges_start,
+                raw_size: nb_edges * size_of::<bindings::alcov_out_edge>() as u64,
+            });
+        }
+
         Ok(AlcovHeaderMetadata {
             version_major,
             version_minor,
             nb_modules,
             nb_blocks,
             nb_edges,
-            modules_start,
-            paths_start,
-            blocks_start,
-            edges_start,
             flags,
+            chunks,
         })
     }
 }
 
 impl AlcovHeader {
+    /// If `compress` is set, chunks are compressed with LZMA2. Chunks are checked with CRC-32C.
     pub fn new<P>(input_path: Option<P>, compress: bool) -> Self
     where
         P: Into<PathBuf>,
     {
+        let codec = if compress {
+            AlcovCodec::Lzma2
+        } else {
+            AlcovCodec::None
+        };
+
         Self {
             version_major: bindings::ALCOV_VERSION_MAJOR,
             version_minor: bindings::ALCOV_VERSION_MINOR,
-            compress,
+            blocks_codec: codec,
+            edges_codec: codec,
+            checksum: Some(AlcovChecksum::Crc32c),
             input_path: input_path.map(Into::into),
+            input: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Use the same codec for every chunk.
+    pub fn set_codec(&mut self, codec: AlcovCodec) {
+        self.blocks_codec = codec;
+        self.edges_codec = codec;
+        if let Some(input) = &mut self.input {
+            input.codec = codec;
         }
     }
+
+    pub fn compress(&self) -> bool {
+        self.blocks_codec != AlcovCodec::None
+            || self.edges_codec != AlcovCodec::None
+            || self
+                .input
+                .as_ref()
+                .is_some_and(|input| input.codec != AlcovCodec::None)
+    }
 }
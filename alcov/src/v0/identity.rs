@@ -0,0 +1,241 @@
+use crate::v0::{AlcovModule, ED, Error, bindings};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use object::read::pe::{ImageNtHeaders, ImageOptionalHeader, PeFile};
+use object::{LittleEndian, Object};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::mem;
+use std::path::Path;
+
+/// Identity of the file backing a module, telling apart different builds with the same
+/// path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AlcovModuleIdentity {
+    /// content of the `NT_GNU_BUILD_ID` note of an ELF file.
+    GnuBuildId(Vec<u8>),
+    /// `TimeDateStamp` and `SizeOfImage` of a PE file.
+    Pe {
+        timestamp: u32,
+        size_of_image: u32,
+    },
+    Sha256([u8; 32]),
+}
+
+impl AlcovModuleIdentity {
+    pub fn kind(&self) -> u8 {
+        let kind = match self {
+            Self::GnuBuildId(_) => bindings::ALCOV_MODULE_ID_GNU_BUILD_ID,
+            Self::Pe { .. } => bindings::ALCOV_MODULE_ID_PE,
+            Self::Sha256(_) => bindings::ALCOV_MODULE_ID_SHA256,
+        };
+
+        kind as u8
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        match self {
+            Self::GnuBuildId(build_id) => build_id.clone(),
+            Self::Pe {
+                timestamp,
+                size_of_image,
+            } => [timestamp.to_le_bytes(), size_of_image.to_le_bytes()].concat(),
+            Self::Sha256(hash) => hash.to_vec(),
+        }
+    }
+
+    pub fn from_data(kind: u8, data: &[u8]) -> Result<Self, Error> {
+        match kind as u32 {
+            bindings::ALCOV_MODULE_ID_GNU_BUILD_ID => Ok(Self::GnuBuildId(data.to_vec())),
+            bindings::ALCOV_MODULE_ID_PE => {
+                if data.len() != 8 {
+                    return Err(Error::MalformedModuleIdentity);
+                }
+
+                let mut data_rdr = Cursor::new(data);
+                let timestamp = data_rdr.read_u32::<ED>()?;
+                let size_of_image = data_rdr.read_u32::<ED>()?;
+
+                Ok(Self::Pe {
+                    timestamp,
+                    size_of_image,
+                })
+            }
+            bindings::ALCOV_MODULE_ID_SHA256 => Ok(Self::Sha256(
+                data.try_into()
+                    .map_err(|_| Error::MalformedModuleIdentity)?,
+            )),
+            _ => Err(Error::WrongModuleIdentity(kind)),
+        }
+    }
+
+    /// Every identity found in the content of a file: its build ID for an ELF file, its
+    /// timestamp and size for a PE file, and its SHA-256 in any case.
+    pub fn from_file_data(data: &[u8]) -> Vec<Self> {
+        let mut identities: Vec<Self> = Vec::new();
+
+        if let Ok(file) = object::File::parse(data) {
+            if let Ok(Some(build_id)) = file.build_id() {
+                identities.push(Self::GnuBuildId(build_id.to_vec()));
+            }
+
+            match &file {
+                object::File::Pe32(pe) => identities.push(Self::from_pe(pe)),
+                object::File::Pe64(pe) => identities.push(Self::from_pe(pe)),
+                _ => {}
+            }
+        }
+
+        identities.push(Self::Sha256(Sha256::digest(data).into()));
+
+        identities
+    }
+
+    fn from_pe<Pe>(pe: &PeFile<Pe>) -> Self
+    where
+        Pe: ImageNtHeaders,
+    {
+        let nt_headers = pe.nt_headers();
+
+        Self::Pe {
+            timestamp: nt_headers.file_header().time_date_stamp.get(LittleEndian),
+            size_of_image: nt_headers.optional_header().size_of_image(),
+        }
+    }
+}
+
+impl AlcovModule {
+    /// Replace the identities of the module by the ones of a file.
+    pub fn identify(&mut self, path: &Path) -> Result<(), Error> {
+        self.identities = AlcovModuleIdentity::from_file_data(&fs::read(path)?);
+
+        Ok(())
+    }
+
+    /// Whether two modules are backed by the same file, according to the identities of the
+    /// same kind they both have. `None` if they have none in common.
+    pub fn same_file(&self, other: &AlcovModule) -> Option<bool> {
+        let mut compared = false;
+
+        for identity in &self.identities {
+            let other_identity = other.identities.iter().find(|other_identity| {
+                mem::discriminant(*other_identity) == mem::discriminant(identity)
+            });
+
+            if let Some(other_identity) = other_identity {
+                if other_identity != identity {
+                    return Some(false);
+                }
+                compared = true;
+            }
+        }
+
+        compared.then_some(true)
+    }
+}
+
+/// Write the module identities chunk, with the identities of every module.
+pub fn write_identities<W>(writer: &mut W, modules: &[AlcovModule]) -> Result<(), Error>
+where
+    W: Write,
+{
+    for (module_id, module) in modules.iter().enumerate() {
+        for identity in &module.identities {
+            let data = identity.data();
+
+            writer.write_u16::<ED>(u16::try_from(module_id)?)?;
+            writer.write_u8(identity.kind())?;
+            writer.write_u8(u8::try_from(data.len())?)?;
+            writer.write_all(&data)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the module identities chunk, adding the identities to their module.
+pub fn read_identities(identities_buf: &[u8], modules: &mut [AlcovModule]) -> Result<(), Error> {
+    let mut identities_rdr = Cursor::new(identities_buf);
+
+    while identities_rdr.position() < identities_buf.len() as u64 {
+        let module_id = identities_rdr.read_u16::<ED>()?;
+        let kind = identities_rdr.read_u8()?;
+        let size = identities_rdr.read_u8()?;
+
+        let mut data = vec![0u8; size as usize];
+        identities_rdr.read_exact(&mut data)?;
+
+        modules
+            .get_mut(module_id as usize)
+            .ok_or(Error::MalformedModuleIdentity)?
+            .identities
+            .push(AlcovModuleIdentity::from_data(kind, &data)?);
+    }
+
+    Ok(())
+}
+
+impl Display for AlcovModuleIdentity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GnuBuildId(build_id) => {
+                write!(f, "build-id ")?;
+                build_id.iter().try_for_each(|b| write!(f, "{b:02x}"))
+            }
+            Self::Pe {
+                timestamp,
+                size_of_image,
+            } => write!(f, "PE timestamp {timestamp:#x}, size {size_of_image:#x}"),
+            Self::Sha256(hash) => {
+                write!(f, "SHA-256 ")?;
+                hash.iter().try_for_each(|b| write!(f, "{b:02x}"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{Alcov, AlcovBlock, AlcovHeader, AlcovSegment};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_identities() {
+        let build_id = AlcovModuleIdentity::GnuBuildId(vec![0xab, 0xcd, 0xef]);
+        let pe = AlcovModuleIdentity::Pe {
+            timestamp: 0x5f3a0000,
+            size_of_image: 0x2000,
+        };
+        let sha256 = AlcovModuleIdentity::from_file_data(b"not an object file");
+        assert!(matches!(sha256[..], [AlcovModuleIdentity::Sha256(_)]));
+
+        let mut modules = vec![
+            AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap(),
+            AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap(),
+        ];
+        modules[0].identities = vec![build_id.clone(), sha256[0].clone()];
+        modules[1].identities = vec![pe];
+
+        let blocks = vec![AlcovBlock::new(1, 0, 0x10, 4, 1)];
+        let alcov = Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, false),
+            modules,
+            blocks,
+            None,
+        );
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+
+        let mut other = alcov.modules[0].clone();
+        assert_eq!(alcov.modules[0].same_file(&other), Some(true));
+        assert_eq!(alcov.modules[0].same_file(&alcov.modules[1]), None);
+
+        other.identities = vec![build_id, AlcovModuleIdentity::Sha256([0; 32])];
+        assert_eq!(alcov.modules[0].same_file(&other), Some(false));
+    }
+}
@@ -0,0 +1,462 @@
+//! Valgrind callgrind profiles.
+//!
+//! Profiles must be recorded with `--dump-instr=yes --collect-jumps=yes`: every executed
+//! instruction then has its own cost line giving its `Ir` (instructions executed) count,
+//! and every jump is recorded as a `jump=<count> <target>` or
+//! `jcnd=<executed>/<taken> <target>` line followed by the position of the jump
+//! instruction.
+//!
+//! Instructions are grouped in blocks, which end at jumps and calls, and start at jump
+//! targets or when the hit count changes. Instruction sizes are not known: when the
+//! following instruction is not reached by falling through, a block ends at the first
+//! byte of its last instruction.
+//!
+//! Modules are taken from a maps file, or built from the ELF file and the addresses of
+//! each `ob=` object.
+
+use crate::v0::import::{AddressBlock, build_alcov};
+use crate::v0::{Alcov, AlcovHeader, AlcovModule, AlcovPerms, AlcovSegment, Error, ProcMaps};
+use object::{Object, ObjectKind};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Build the module of an object from its ELF file, given its lowest executed address.
+///
+/// Executables are loaded at their link address. Other objects are assumed to have their
+/// lowest executed address in their first executable segment, at a page-aligned load
+/// bias: the base address is exact as long as this segment is smaller than a page, and
+/// may be a few pages too high otherwise.
+fn elf_module(path: &str, start: u64) -> Option<AlcovModule> {
+    let data = fs::read(path).ok()?;
+    let file = object::File::parse(&*data).ok()?;
+
+    let mut module = AlcovModule::from_elf_data(path, &data, 0).ok()?;
+    if file.kind() != ObjectKind::Executable {
+        let text = module
+            .segments
+            .iter()
+            .find(|segment| segment.perms.contains(AlcovPerms::Execute))?;
+        module.base_address = start.checked_sub(text.module_range.start)? & !0xfff;
+    }
+
+    Some(module)
+}
+
+/// Above this distance, two instructions cannot be consecutive.
+const MAX_INSN_SIZE: u64 = 15;
+
+/// An executed instruction.
+#[derive(Debug, Clone, Copy, Default)]
+struct Instruction {
+    object: usize,
+    nb_taken: u64,
+}
+
+/// A jump waiting for the position of its source.
+#[derive(Debug, Clone, Copy)]
+struct PendingJump {
+    target: u64,
+    nb_taken: u64,
+    conditional: bool,
+    nb_fallthrough: u64,
+}
+
+/// Parsing state of a single profile file, since positions and compressed names are
+/// relative to it.
+#[derive(Debug, Clone, Default)]
+struct FileState {
+    /// index of the instruction address in positions, `None` without `--dump-instr=yes`.
+    instr_position: Option<usize>,
+    nb_positions: usize,
+    ir_event: usize,
+    /// compressed object names to object index.
+    objects: HashMap<String, usize>,
+    object: Option<usize>,
+    last_address: u64,
+    /// the next cost line is the inclusive cost of a call.
+    call_cost: bool,
+    jump: Option<PendingJump>,
+}
+
+/// Build an alcov trace from callgrind profiles.
+#[derive(Debug, Clone, Default)]
+pub struct CallgrindImporter {
+    objects: Vec<String>,
+    instructions: BTreeMap<u64, Instruction>,
+    /// jump (source, target) to number of times taken.
+    jumps: HashMap<(u64, u64), u64>,
+    /// jump and call sources.
+    block_ends: HashSet<u64>,
+    /// jump and call sources followed by their next instruction, with the number of times
+    /// the conditional jumps were not taken.
+    fallthroughs: HashMap<u64, u64>,
+    /// jump targets.
+    leaders: HashSet<u64>,
+}
+
+fn parse_number(number: &str) -> Option<u64> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
+}
+
+/// Parse a position, absolute (`0x401136`), relative (`+3`, `-2`) or unchanged (`*`).
+fn parse_position(position: &str, last: u64) -> Option<u64> {
+    if position == "*" {
+        Some(last)
+    } else if let Some(diff) = position.strip_prefix('+') {
+        Some(last.wrapping_add(parse_number(diff)?))
+    } else if let Some(diff) = position.strip_prefix('-') {
+        Some(last.wrapping_sub(parse_number(diff)?))
+    } else {
+        parse_number(position)
+    }
+}
+
+/// Parse the positions at the start of a line, returning the instruction address
+/// and the remaining fields.
+fn parse_positions<'a>(state: &mut FileState, line: &'a str) -> Option<(u64, Vec<&'a str>)> {
+    let mut fields: Vec<&str> = line.split_whitespace().collect();
+    let instr_position = state.instr_position?;
+
+    let address = parse_position(fields.get(instr_position)?, state.last_address)?;
+    state.last_address = address;
+
+    let nb_positions = state.nb_positions.min(fields.len());
+    Some((address, fields.split_off(nb_positions)))
+}
+
+impl CallgrindImporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a callgrind profile. Unknown lines are ignored.
+    pub fn read<R>(&mut self, reader: R) -> Result<(), Error>
+    where
+        R: BufRead,
+    {
+        let mut state = FileState {
+            nb_positions: 1,
+            ..FileState::default()
+        };
+
+        for (i, line) in reader.lines().enumerate() {
+            self.parse_line(&mut state, &line?)
+                .ok_or(Error::MalformedCallgrind { line: i + 1 })?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolve an object name, which may be compressed as `(id) name` or `(id)`.
+    fn object(&mut self, state: &mut FileState, name: &str) -> usize {
+        let (id, name) = match name.strip_prefix('(').and_then(|name| name.split_once(')')) {
+            Some((id, name)) => (Some(id), name.trim()),
+            None => (None, name.trim()),
+        };
+
+        if name.is_empty()
+            && let Some(object) = id.and_then(|id| state.objects.get(id))
+        {
+            return *object;
+        }
+
+        let object = match self.objects.iter().position(|object| object == name) {
+            Some(object) => object,
+            None => {
+                self.objects.push(name.to_string());
+                self.objects.len() - 1
+            }
+        };
+
+        if let Some(id) = id {
+            state.objects.insert(id.to_string(), object);
+        }
+
+        object
+    }
+
+    fn parse_line(&mut self, state: &mut FileState, line: &str) -> Option<()> {
+        let Some(first) = line.chars().next() else {
+            return Some(());
+        };
+
+        if first.is_ascii_digit() || matches!(first, '+' | '-' | '*') {
+            let (address, costs) = parse_positions(state, line)?;
+
+            if let Some(jump) = state.jump.take() {
+                *self.jumps.entry((address, jump.target)).or_default() += jump.nb_taken;
+                self.block_ends.insert(address);
+                if jump.conditional {
+                    *self.fallthroughs.entry(address).or_default() += jump.nb_fallthrough;
+                }
+            } else if state.call_cost {
+                state.call_cost = false;
+                self.block_ends.insert(address);
+                self.fallthroughs.entry(address).or_default();
+            } else if let Some(object) = state.object {
+                let nb_taken = costs
+                    .get(state.ir_event)
+                    .map_or(Some(0), |cost| parse_number(cost))?;
+
+                if nb_taken > 0 {
+                    let instruction = self.instructions.entry(address).or_insert(Instruction {
+                        object,
+                        nb_taken: 0,
+                    });
+                    instruction.nb_taken += nb_taken;
+                }
+            }
+
+            return Some(());
+        }
+
+        if let Some(positions) = line.strip_prefix("positions:") {
+            let positions: Vec<&str> = positions.split_whitespace().collect();
+            state.instr_position = positions.iter().position(|position| *position == "instr");
+            state.nb_positions = positions.len();
+        } else if let Some(events) = line.strip_prefix("events:") {
+            state.ir_event = events
+                .split_whitespace()
+                .position(|event| event == "Ir")
+                .unwrap_or(0);
+        } else if let Some(name) = line.strip_prefix("ob=") {
+            state.object = Some(self.object(state, name));
+        } else if let Some(name) = line.strip_prefix("cob=") {
+            // only defines the compressed name.
+            self.object(state, name);
+        } else if let Some(calls) = line.strip_prefix("calls=") {
+            let mut fields = calls.split_whitespace();
+            let _nb_calls = fields.next()?;
+            state.last_address = parse_position(fields.next()?, state.last_address)?;
+            state.call_cost = true;
+        } else if let Some(jump) = line.strip_prefix("jump=") {
+            let mut fields = jump.split_whitespace();
+            let nb_taken = parse_number(fields.next()?)?;
+            let target = parse_position(fields.next()?, state.last_address)?;
+
+            state.last_address = target;
+            state.jump = Some(PendingJump {
+                target,
+                nb_taken,
+                conditional: false,
+                nb_fallthrough: 0,
+            });
+            self.leaders.insert(target);
+        } else if let Some(jcnd) = line.strip_prefix("jcnd=") {
+            let mut fields = jcnd.split_whitespace();
+            let (nb_executed, nb_taken) = fields.next()?.split_once('/')?;
+            let nb_executed = parse_number(nb_executed)?;
+            let nb_taken = parse_number(nb_taken)?;
+            let target = parse_position(fields.next()?, state.last_address)?;
+
+            state.last_address = target;
+            state.jump = Some(PendingJump {
+                target,
+                nb_taken,
+                conditional: true,
+                nb_fallthrough: nb_executed.saturating_sub(nb_taken),
+            });
+            self.leaders.insert(target);
+        }
+
+        Some(())
+    }
+
+    /// Group instructions in blocks. Returns the blocks, their object, and the block of
+    /// each instruction.
+    fn blocks(&self) -> (Vec<AddressBlock>, Vec<usize>, HashMap<u64, usize>) {
+        let mut blocks: Vec<AddressBlock> = Vec::new();
+        let mut objects: Vec<usize> = Vec::new();
+        let mut block_ids: HashMap<u64, usize> = HashMap::new();
+
+        let mut instructions = self.instructions.iter().peekable();
+        let mut prev: Option<(u64, Instruction)> = None;
+
+        while let Some((address, instruction)) = instructions.next() {
+            let new_block = match prev {
+                None => true,
+                Some((prev_address, prev_instruction)) => {
+                    self.block_ends.contains(&prev_address)
+                        || self.leaders.contains(address)
+                        || address - prev_address > MAX_INSN_SIZE
+                        || prev_instruction.object != instruction.object
+                        || prev_instruction.nb_taken != instruction.nb_taken
+                }
+            };
+
+            if new_block {
+                blocks.push(AddressBlock {
+                    address: *address,
+                    size: 0,
+                    nb_taken: instruction.nb_taken,
+                });
+                objects.push(instruction.object);
+            }
+
+            block_ids.insert(*address, blocks.len() - 1);
+            let block = blocks.last_mut().unwrap();
+
+            // the block goes up to the next instruction if it is reached by falling through.
+            let end = match instructions.peek() {
+                Some((next, _))
+                    if *next - address <= MAX_INSN_SIZE
+                        && (!self.block_ends.contains(address)
+                            || self.fallthroughs.contains_key(address)) =>
+                {
+                    **next
+                }
+                _ => address + 1,
+            };
+            block.size = u32::try_from(end - block.address).unwrap_or(0);
+
+            prev = Some((*address, *instruction));
+        }
+
+        (blocks, objects, block_ids)
+    }
+
+    /// Build the trace.
+    ///
+    /// Addresses are mapped to the modules of `maps`. Without maps, the module of each
+    /// object is built from its ELF file when it can be read, guessing the load bias of
+    /// position-independent objects. Otherwise, it has a single segment covering its
+    /// executed instructions, with the page of the first one as approximate base address.
+    pub fn finish(self, maps: Option<&ProcMaps>, hdr: AlcovHeader) -> Result<Alcov, Error> {
+        let (blocks, block_objects, block_ids) = self.blocks();
+
+        let modules = match maps {
+            Some(maps) => maps.modules(),
+            None => {
+                let mut ranges: BTreeMap<usize, (u64, u64)> = BTreeMap::new();
+                for (block, object) in blocks.iter().zip(&block_objects) {
+                    let end = block.address + block.size.max(1) as u64;
+                    let range = ranges.entry(*object).or_insert((block.address, end));
+                    *range = (range.0.min(block.address), range.1.max(end));
+                }
+
+                ranges
+                    .into_iter()
+                    .map(|(object, (start, end))| {
+                        let base_address = start & !0xfff;
+                        let path = &self.objects[object];
+
+                        if let Some(module) = elf_module(path, start) {
+                            return Ok(module);
+                        }
+
+                        AlcovModule::new(
+                            base_address,
+                            (path != "???").then(|| PathBuf::from(path)),
+                            vec![AlcovSegment::new(
+                                0..(end - base_address).next_multiple_of(0x1000),
+                            )],
+                        )
+                    })
+                    .collect::<Result<Vec<AlcovModule>, Error>>()?
+            }
+        };
+
+        let mut edges: HashMap<(usize, usize), u64> = HashMap::new();
+        for ((src, dst), nb_taken) in &self.jumps {
+            if let (Some(src_id), Some(dst_id)) = (block_ids.get(src), block_ids.get(dst)) {
+                *edges.entry((*src_id, *dst_id)).or_default() += nb_taken;
+            }
+        }
+
+        // the not taken side of conditional jumps.
+        for (src, nb_fallthrough) in &self.fallthroughs {
+            let next = self.instructions.range((src + 1)..).next();
+
+            if *nb_fallthrough > 0
+                && let (Some(src_id), Some((next, _))) = (block_ids.get(src), next)
+                && next - src <= MAX_INSN_SIZE
+            {
+                *edges.entry((*src_id, block_ids[next])).or_default() += nb_fallthrough;
+            }
+        }
+
+        build_alcov(hdr, modules, &blocks, Some(&edges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{AlcovBlock, AlcovDstBlockEdgeMetadata};
+    use std::io::Cursor;
+
+    const PROFILE: &str = "\
+version: 1
+creator: callgrind-3.22.0
+positions: instr line
+events: Ir
+
+ob=(1) /usr/bin/target
+fl=(1) main.c
+fn=(1) main
+0x401000 3 1
++2 * 1
++3 4 10
++2 * 10
++3 * 10
+jcnd=10/9 -5 *
++5 *
++4 5 1
++3 * 1
+calls=1 0x401100 8
+0x401011 * 3
++8 6 1
+jump=1 +23 *
+-23 *
++23 7 1
+";
+
+    #[test]
+    fn test_callgrind_profile() {
+        let mut importer = CallgrindImporter::new();
+        importer.read(Cursor::new(PROFILE)).unwrap();
+
+        let alcov = importer
+            .finish(None, AlcovHeader::new(None::<PathBuf>, false))
+            .unwrap();
+
+        assert_eq!(alcov.modules.len(), 1);
+        assert_eq!(alcov.modules[0].base_address, 0x401000);
+        assert_eq!(
+            alcov.modules[0].path,
+            Some(PathBuf::from("/usr/bin/target"))
+        );
+
+        // the call returns to 0x401019, in a new block.
+        assert_eq!(
+            alcov.blocks,
+            vec![
+                AlcovBlock::new(0, 0, 0, 5, 1),
+                AlcovBlock::new(0, 0, 0x5, 0x9, 10),
+                AlcovBlock::new(0, 0, 0xe, 0xb, 1),
+                AlcovBlock::new(0, 0, 0x19, 1, 1),
+                AlcovBlock::new(0, 0, 0x30, 1, 1),
+            ]
+        );
+
+        let edges = alcov.edges.unwrap();
+        assert_eq!(edges.nb_edges(), 3);
+        assert_eq!(
+            edges.adj_list[1].dst_modules[&1.into()],
+            AlcovDstBlockEdgeMetadata { nb_taken: 9 }
+        );
+        assert_eq!(
+            edges.adj_list[1].dst_modules[&2.into()],
+            AlcovDstBlockEdgeMetadata { nb_taken: 1 }
+        );
+        assert_eq!(
+            edges.adj_list[3].dst_modules[&4.into()],
+            AlcovDstBlockEdgeMetadata { nb_taken: 1 }
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Linux kernel coverage, from kcov buffers and syzkaller cover lists.
+//!
+//! kcov (`KCOV_TRACE_PC`) fills a buffer of machine words whose first word is the number
+//! of PCs following it, one per executed coverage callback. syzkaller stores the same PCs
+//! as a textual list of hexadecimal values, one per line, possibly truncated to 32 bits.
+//!
+//! PCs are return addresses of the coverage callbacks, at runtime addresses: they are
+//! mapped back to `vmlinux` using the KASLR offset, and to kernel modules (`.ko`) using
+//! their load address from `/proc/modules`.
+
+use crate::v0::{
+    Alcov, AlcovArch, AlcovBlock, AlcovHeader, AlcovModule, AlcovModuleIdentity, AlcovPerms,
+    AlcovSegment, ED, Error,
+};
+use byteorder::ReadBytesExt;
+use object::{Architecture, Object, ObjectSection, ObjectSymbol};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// A kernel module loaded in memory, as found in `/proc/modules`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcModule {
+    pub name: String,
+    pub size: u64,
+    pub address: u64,
+}
+
+/// Read a kcov buffer dump (64-bit words).
+pub fn read_kcov<R>(reader: &mut R) -> Result<Vec<u64>, Error>
+where
+    R: Read,
+{
+    let nb_pcs = reader.read_u64::<ED>()?;
+
+    let mut pcs_buf = Vec::new();
+    reader.read_to_end(&mut pcs_buf)?;
+
+    Ok(pcs_buf
+        .chunks_exact(8)
+        .take(usize::try_from(nb_pcs)?)
+        .map(|pc| u64::from_le_bytes(pc.try_into().unwrap()))
+        .collect())
+}
+
+/// Parse a syzkaller cover list.
+///
+/// On 64-bit kernels, PCs truncated to 32 bits get their upper half restored, as kernel
+/// addresses.
+pub fn parse_pc_list(list: &str, is_64: bool) -> Vec<u64> {
+    list.split_whitespace()
+        .filter_map(|pc| u64::from_str_radix(pc.strip_prefix("0x").unwrap_or(pc), 16).ok())
+        .map(|pc| {
+            if is_64 && pc <= u32::MAX as u64 {
+                pc | 0xffffffff00000000
+            } else {
+                pc
+            }
+        })
+        .collect()
+}
+
+/// Parse a `/proc/modules` dump. Malformed lines are ignored.
+pub fn parse_proc_modules(modules: &str) -> Vec<ProcModule> {
+    modules
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let address = fields.get(5)?;
+
+            Some(ProcModule {
+                name: fields[0].to_string(),
+                size: fields[1].parse().ok()?,
+                address: u64::from_str_radix(address.strip_prefix("0x")?, 16).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Compute the KASLR offset, comparing the address of `_text` in a `/proc/kallsyms` dump
+/// with the one in the content of `vmlinux`.
+pub fn kaslr_offset(vmlinux: &[u8], kallsyms: &str) -> Result<u64, Error> {
+    let runtime_text = kallsyms
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let address = fields.next()?;
+            let name = fields.nth(1)?;
+
+            (name == "_text")
+                .then(|| u64::from_str_radix(address, 16).ok())
+                .flatten()
+        })
+        .ok_or(Error::MissingSymbol("_text".to_string()))?;
+
+    let file = object::File::parse(vmlinux).map_err(|_| Error::MalformedBinary)?;
+    let link_text = file
+        .symbols()
+        .find(|symbol| symbol.name() == Ok("_text"))
+        .ok_or(Error::MissingSymbol("_text".to_string()))?
+        .address();
+
+    Ok(runtime_text.wrapping_sub(link_text))
+}
+
+/// Size of the call instruction before a return address.
+fn call_size(architecture: Architecture) -> u64 {
+    match architecture {
+        Architecture::X86_64 | Architecture::I386 => 5,
+        Architecture::S390x => 6,
+        Architecture::Mips | Architecture::Mips64 => 8,
+        Architecture::Aarch64
+        | Architecture::Arm
+        | Architecture::Riscv64
+        | Architecture::PowerPc64 => 4,
+        _ => 1,
+    }
+}
+
+/// Build an alcov trace from kernel PCs.
+///
+/// The coverage callbacks are inserted at the start of each basic block, but their
+/// position in the block is not known: blocks start at the callback call, with an
+/// unknown (0) size.
+#[derive(Debug, Clone)]
+pub struct KcovImporter {
+    modules: Vec<AlcovModule>,
+    call_size: u64,
+    is_64: bool,
+    blocks: Vec<AlcovBlock>,
+    /// (module ID, offset from module base) to block ID.
+    block_ids: HashMap<(u16, u64), usize>,
+}
+
+impl KcovImporter {
+    /// Start an import for a `vmlinux` loaded with a KASLR offset, given its path and
+    /// content.
+    pub fn new(vmlinux_path: &Path, vmlinux: &[u8], kaslr_offset: u64) -> Result<Self, Error> {
+        let file = object::File::parse(vmlinux).map_err(|_| Error::MalformedBinary)?;
+
+        let mut module = AlcovModule::from_elf_data(vmlinux_path, vmlinux, 0)?;
+        module.base_address = module.base_address.wrapping_add(kaslr_offset);
+
+        Ok(Self {
+            modules: vec![module],
+            call_size: call_size(file.architecture()),
+            is_64: file.is_64(),
+            blocks: Vec::new(),
+            block_ids: HashMap::new(),
+        })
+    }
+
+    /// Whether the kernel is a 64-bit one, e.g. to parse cover lists.
+    pub fn is_64(&self) -> bool {
+        self.is_64
+    }
+
+    /// Add a kernel module, loaded at the address given by `/proc/modules`.
+    ///
+    /// The kernel places the `.text` section of the module at its load address: it becomes
+    /// the single segment of the module, and PCs in the rest of the allocation are ignored.
+    pub fn add_module(&mut self, ko: &Path, proc_module: &ProcModule) -> Result<(), Error> {
+        let data = fs::read(ko)?;
+        let file = object::File::parse(&*data).map_err(|_| Error::MalformedBinary)?;
+        let text = file
+            .section_by_name(".text")
+            .ok_or(Error::MalformedBinary)?;
+
+        let mut segment = AlcovSegment::new(0..text.size().min(proc_module.size));
+        segment.file_offset = text.file_range().map(|(offset, _)| offset);
+        segment.perms = AlcovPerms::Read | AlcovPerms::Execute;
+
+        let mut module =
+            AlcovModule::new(proc_module.address, Some(ko.to_path_buf()), vec![segment])?;
+        module.identities = AlcovModuleIdentity::from_file_data(&data);
+        module.arch = AlcovArch::from_object(&file);
+        self.modules.push(module);
+
+        Ok(())
+    }
+
+    /// Add runtime PCs. If `counted` is set, each occurrence of a PC increments the
+    /// `nb_taken` counter of its block. Otherwise, it is left unknown.
+    ///
+    /// PCs outside of every module are ignored.
+    pub fn add_pcs(&mut self, pcs: &[u64], counted: bool) -> Result<(), Error> {
+        for pc in pcs {
+            let call = pc.wrapping_sub(self.call_size);
+
+            let Some((module_id, (segment_id, segment_offset))) = self
+                .modules
+                .iter()
+                .enumerate()
+                .find_map(|(module_id, module)| {
+                    let location = module.locate(call.wrapping_sub(module.base_address))?;
+                    Some((module_id, location))
+                })
+            else {
+                continue;
+            };
+
+            let module_id = u16::try_from(module_id)?;
+            let offset = call.wrapping_sub(self.modules[module_id as usize].base_address);
+            let block_id = *self
+                .block_ids
+                .entry((module_id, offset))
+                .or_insert_with(|| {
+                    self.blocks
+                        .push(AlcovBlock::new(module_id, segment_id, segment_offset, 0, 0));
+                    self.blocks.len() - 1
+                });
+
+            if counted {
+                self.blocks[block_id].nb_taken += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self, hdr: AlcovHeader) -> Alcov {
+        Alcov::new(hdr, self.modules, self.blocks, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_kcov_inputs() {
+        let mut kcov: Vec<u8> = Vec::new();
+        for word in [2u64, 0xffffffff81001005, 0xffffffff81002005, 0xdeadbeef] {
+            kcov.extend_from_slice(&word.to_le_bytes());
+        }
+        assert_eq!(
+            read_kcov(&mut Cursor::new(kcov)).unwrap(),
+            vec![0xffffffff81001005, 0xffffffff81002005]
+        );
+
+        assert_eq!(
+            parse_pc_list("0xffffffff81001005\n81002005\n", true),
+            vec![0xffffffff81001005, 0xffffffff81002005]
+        );
+        assert_eq!(parse_pc_list("c1002005\n", false), vec![0xc1002005]);
+
+        let modules = parse_proc_modules(
+            "nf_tables 299008 1 nft_chain_nat, Live 0xffffffffc0a4e000\n\
+             e1000 155648 0 - Live 0xffffffffc0123000 (O)\n",
+        );
+        assert_eq!(
+            modules[1],
+            ProcModule {
+                name: "e1000".to_string(),
+                size: 155648,
+                address: 0xffffffffc0123000,
+            }
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Importers from other coverage formats.
+
+use crate::v0::{
+    Alcov, AlcovBlock, AlcovBlockEdges, AlcovDstBlockEdgeMetadata, AlcovEdges, AlcovHeader,
+    AlcovModule, Error,
+};
+use std::collections::HashMap;
+
+pub mod callgrind;
+pub mod kcov;
+pub mod perf;
+pub mod qemu;
+pub mod sancov;
+
+/// A block found at an absolute address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressBlock {
+    pub address: u64,
+    pub size: u32,
+    pub nb_taken: u64,
+}
+
+/// Place blocks found at absolute addresses in modules, and build the trace.
+///
+/// `edges` maps pairs of (source, destination) indices in `blocks` to the number of times
+/// the edge has been taken. Blocks outside of every module are dropped, along with their
+/// edges.
+pub fn build_alcov(
+    hdr: AlcovHeader,
+    modules: Vec<AlcovModule>,
+    blocks: &[AddressBlock],
+    edges: Option<&HashMap<(usize, usize), u64>>,
+) -> Result<Alcov, Error> {
+    let mut alcov_blocks: Vec<AlcovBlock> = Vec::new();
+    let mut block_ids: HashMap<usize, u64> = HashMap::new();
+
+    for (i, block) in blocks.iter().enumerate() {
+        let Some((module_id, (segment_id, segment_offset))) =
+            modules.iter().enumerate().find_map(|(module_id, module)| {
+                module
+                    .locate(block.address.wrapping_sub(module.base_address))
+                    .map(|location| (module_id, location))
+            })
+        else {
+            continue;
+        };
+
+        block_ids.insert(i, alcov_blocks.len() as u64);
+        alcov_blocks.push(AlcovBlock::new(
+            u16::try_from(module_id)?,
+            segment_id,
+            segment_offset,
+            block.size,
+            block.nb_taken,
+        ));
+    }
+
+    let alcov_edges = edges.map(|edges| {
+        let mut alcov_edges = AlcovEdges::new();
+        alcov_edges
+            .adj_list
+            .resize(alcov_blocks.len(), AlcovBlockEdges::default());
+
+        for ((src, dst), nb_taken) in edges {
+            let (Some(src_id), Some(dst_id)) = (block_ids.get(src), block_ids.get(dst)) else {
+                continue;
+            };
+
+            alcov_edges.adj_list[*src_id as usize].dst_modules.insert(
+                (*dst_id).into(),
+                AlcovDstBlockEdgeMetadata {
+                    nb_taken: *nb_taken,
+                },
+            );
+        }
+
+        alcov_edges
+    });
+
+    Ok(Alcov::new(hdr, modules, alcov_blocks, alcov_edges))
+}
@@ -0,0 +1,269 @@
+//! `perf script` branch stacks (LBR).
+//!
+//! Samples recorded with `perf record -b` are printed by `perf script -F ip,brstack` as a
+//! list of `0x<from>/0x<to>/<flags>...` taken branches, the most recent first. Code
+//! executed between the target of a branch and the source of the next one is
+//! straight-line: each such range becomes a block, and each branch an edge between two
+//! of them. Counters are sampled, so they are only relative to each other.
+//!
+//! The source of a branch is the address of the branch instruction, whose size is not
+//! known: blocks end at the first byte of their last instruction. The sample IP (`ip`
+//! field) ends the block following the most recent branch. The block preceding the
+//! oldest branch has no known start, so this branch only starts a block, without edge.
+//!
+//! Modules are taken from the `PERF_RECORD_MMAP`/`PERF_RECORD_MMAP2` events printed with
+//! `--show-mmap-events`, or from a maps file. As only executable mappings have events by
+//! default, the base address of a file is its lowest mapping minus its file offset.
+
+use crate::v0::import::{AddressBlock, build_alcov};
+use crate::v0::{
+    Alcov, AlcovHeader, AlcovModule, Error, MapsBacking, MapsEntry, MapsPerms, ProcMaps,
+};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Build an alcov trace from `perf script` output.
+#[derive(Debug, Clone, Default)]
+pub struct PerfScriptImporter {
+    blocks: Vec<AddressBlock>,
+    /// (start, end) address to block index.
+    block_ids: HashMap<(u64, u64), usize>,
+    edges: HashMap<(usize, usize), u64>,
+    mmaps: ProcMaps,
+}
+
+fn parse_hex(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex.strip_prefix("0x")?, 16).ok()
+}
+
+/// Parse a branch of a `brstack` field, like `0x401150/0x401136/P/-/-/0`.
+fn parse_branch(token: &str) -> Option<(u64, u64)> {
+    let mut fields = token.split('/');
+    let from = parse_hex(fields.next()?)?;
+    let to = parse_hex(fields.next()?)?;
+
+    Some((from, to))
+}
+
+/// Parse a mmap event, like
+/// `PERF_RECORD_MMAP2 1234/1234: [0x55d0c4a02000(0x5000) @ 0x2000 fd:01 1835017 0]: r-xp /usr/bin/cat`.
+fn parse_mmap(line: &str) -> Option<MapsEntry> {
+    let (_, event) = line.split_once("PERF_RECORD_MMAP")?;
+    let (_, event) = event.split_once('[')?;
+    let (mapping, rest) = event.split_once("]: ")?;
+
+    let (start, mapping) = mapping.split_once('(')?;
+    let (size, mapping) = mapping.split_once(')')?;
+    let start = parse_hex(start)?;
+    let size = parse_hex(size)?;
+
+    let mut fields = mapping.split_whitespace();
+    let _at = fields.next()?;
+    let pgoff = fields.next()?;
+    let file_offset = parse_hex(pgoff).unwrap_or(0);
+
+    // MMAP2 events give `maj:min inode generation`, or a build ID.
+    let inode = match (fields.next(), fields.next()) {
+        (Some(dev), Some(inode)) if dev.contains(':') => inode.parse().unwrap_or(0),
+        _ => 0,
+    };
+
+    let (perms, path) = rest.split_once(' ')?;
+    let path = path.trim();
+
+    // MMAP events only give `x` or `r`.
+    let perms = if perms.len() == 4 {
+        let perms = perms.as_bytes();
+        MapsPerms {
+            read: perms[0] == b'r',
+            write: perms[1] == b'w',
+            execute: perms[2] == b'x',
+            shared: perms[3] == b's',
+        }
+    } else {
+        MapsPerms {
+            read: true,
+            execute: perms == "x",
+            ..MapsPerms::default()
+        }
+    };
+
+    let backing = if path.starts_with('/') {
+        MapsBacking::File {
+            path: PathBuf::from(path),
+            inode,
+        }
+    } else {
+        MapsBacking::Named(path.to_string())
+    };
+
+    Some(MapsEntry {
+        range: start..(start + size),
+        perms,
+        file_offset,
+        backing,
+    })
+}
+
+/// Move the base address of a file module to the start of the file, when its first
+/// mapping does not start at file offset 0.
+fn to_image_base(module: &mut AlcovModule) {
+    let Some(file_offset) = module
+        .segments
+        .iter()
+        .find(|segment| segment.module_range.start == 0)
+        .and_then(|segment| segment.file_offset)
+    else {
+        return;
+    };
+
+    if file_offset == 0 || module.base_address < file_offset {
+        return;
+    }
+
+    module.base_address -= file_offset;
+    for segment in &mut module.segments {
+        segment.module_range =
+            (segment.module_range.start + file_offset)..(segment.module_range.end + file_offset);
+    }
+}
+
+impl PerfScriptImporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read<R>(&mut self, reader: R) -> Result<(), Error>
+    where
+        R: BufRead,
+    {
+        for line in reader.lines() {
+            self.parse_line(&line?);
+        }
+
+        Ok(())
+    }
+
+    fn block(&mut self, start: u64, end: u64) -> Option<usize> {
+        if end < start {
+            // inconsistent stack (e.g. interrupted between the two branches).
+            return None;
+        }
+
+        let size = u32::try_from(end - start + 1).ok()?;
+
+        let block_id = *self.block_ids.entry((start, end)).or_insert_with(|| {
+            self.blocks.push(AddressBlock {
+                address: start,
+                size,
+                nb_taken: 0,
+            });
+            self.blocks.len() - 1
+        });
+        self.blocks[block_id].nb_taken += 1;
+
+        Some(block_id)
+    }
+
+    /// Parse a line of `perf script` output. Unknown lines are ignored.
+    pub fn parse_line(&mut self, line: &str) {
+        if line.contains("PERF_RECORD_MMAP") {
+            if let Some(entry) = parse_mmap(line) {
+                self.mmaps.entries.push(entry);
+            }
+            return;
+        }
+
+        // most recent branch first.
+        let branches: Vec<(u64, u64)> = line.split_whitespace().filter_map(parse_branch).collect();
+        let Some(&(_, last_to)) = branches.first() else {
+            return;
+        };
+
+        // `ip` is printed first, without `0x` prefix.
+        let ip = line
+            .split_whitespace()
+            .next()
+            .filter(|token| !token.contains('/'))
+            .and_then(|token| u64::from_str_radix(token, 16).ok());
+
+        // block 0 is executed from the last branch to the sample IP, and block i + 1
+        // between branch i + 1 and branch i.
+        let mut blocks: Vec<Option<usize>> = vec![ip.and_then(|ip| self.block(last_to, ip))];
+        for pair in branches.windows(2) {
+            let (from, _) = pair[0];
+            let (_, to) = pair[1];
+            blocks.push(self.block(to, from));
+        }
+
+        for pair in blocks.windows(2) {
+            if let [Some(dst), Some(src)] = pair {
+                *self.edges.entry((*src, *dst)).or_default() += 1;
+            }
+        }
+    }
+
+    /// Build the trace.
+    ///
+    /// Addresses are mapped to the modules of `maps`, or to the mmap events found in the
+    /// output.
+    pub fn finish(self, maps: Option<&ProcMaps>, hdr: AlcovHeader) -> Result<Alcov, Error> {
+        let mut modules = maps.unwrap_or(&self.mmaps).modules();
+        modules.iter_mut().for_each(to_image_base);
+
+        build_alcov(hdr, modules, &self.blocks, Some(&self.edges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{AlcovBlock, AlcovDstBlockEdgeMetadata};
+    use std::io::Cursor;
+
+    const SCRIPT: &str = "\
+perf 1234 [000] 1.000: PERF_RECORD_MMAP2 1234/1234: [0x401000(0x1000) @ 0x1000 fd:01 42 0]: r-xp /usr/bin/target
+             401040 0x401030/0x401040/P/-/-/1 0x401010/0x401020/P/-/-/2 0x401008/0x401000/P/-/-/3
+             401040 0x401030/0x401040/P/-/-/1 0x401010/0x401020/P/-/-/2 0x401018/0x401014/P/-/-/3
+";
+
+    #[test]
+    fn test_perf_script() {
+        let mut importer = PerfScriptImporter::new();
+        importer.read(Cursor::new(SCRIPT)).unwrap();
+
+        let alcov = importer
+            .finish(None, AlcovHeader::new(None::<PathBuf>, false))
+            .unwrap();
+
+        assert_eq!(alcov.modules.len(), 1);
+        assert_eq!(
+            alcov.modules[0].path,
+            Some(PathBuf::from("/usr/bin/target"))
+        );
+        assert_eq!(alcov.modules[0].base_address, 0x400000);
+        assert_eq!(alcov.modules[0].segments[0].module_range, 0x1000..0x2000);
+
+        // the oldest branch of the second sample goes after the next one (0x401014 > 0x401010).
+        assert_eq!(
+            alcov.blocks,
+            vec![
+                AlcovBlock::new(0, 0, 0x40, 1, 2),
+                AlcovBlock::new(0, 0, 0x20, 0x11, 2),
+                AlcovBlock::new(0, 0, 0, 0x11, 1),
+            ]
+        );
+
+        let edges = alcov.edges.unwrap();
+        assert_eq!(edges.nb_edges(), 2);
+        assert_eq!(
+            edges.adj_list[1].dst_modules[&0.into()],
+            AlcovDstBlockEdgeMetadata { nb_taken: 2 }
+        );
+        assert_eq!(
+            edges.adj_list[2].dst_modules[&1.into()],
+            AlcovDstBlockEdgeMetadata { nb_taken: 1 }
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! QEMU execution logs.
+//!
+//! The following log items (`-d <items>`) are understood, and can be mixed in the same log:
+//! - `exec`: every executed translation block (TB) is logged as a
+//!   `Trace <cpu>: <host ptr> [<cs_base>/<pc>/...] <symbol>` line. Executions give the
+//!   `nb_taken` counters, and consecutive executions on a CPU give the edges.
+//! - `in_asm`: every translated TB is disassembled after an `IN:` line, which gives the
+//!   size of the blocks.
+//! - `page`: the guest memory layout (linux-user only), used to find modules when no maps
+//!   file is provided.
+
+use crate::v0::import::{AddressBlock, build_alcov};
+use crate::v0::{
+    Alcov, AlcovHeader, AlcovModule, AlcovSegment, Error, MapsBacking, MapsEntry, MapsPerms,
+    ProcMaps,
+};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// TB being parsed in an `in_asm` log.
+#[derive(Debug, Clone, Default)]
+struct InAsmBlock {
+    start: Option<u64>,
+    /// address of the last instruction, and its size if known.
+    last: Option<(u64, Option<u64>)>,
+}
+
+/// Build an alcov trace from QEMU logs.
+#[derive(Debug, Clone, Default)]
+pub struct QemuLogImporter {
+    sizes: HashMap<u64, u32>,
+    nb_taken: HashMap<u64, u64>,
+    edges: HashMap<(u64, u64), u64>,
+    last_pc: HashMap<u32, u64>,
+    in_asm: Option<InAsmBlock>,
+    page_layout: Option<ProcMaps>,
+}
+
+/// Parse a `Trace` (or `Chain`) line of an `exec` log, returning the CPU index and the
+/// guest PC.
+fn parse_exec(line: &str) -> Option<(u32, u64)> {
+    let rest = line
+        .strip_prefix("Trace ")
+        .or_else(|| line.strip_prefix("Chain "))?;
+
+    // old QEMU versions do not print the CPU index.
+    let cpu = match rest.split_once(':') {
+        Some((cpu, _)) => cpu.trim().parse().unwrap_or(0),
+        None => 0,
+    };
+
+    let (_, bracket) = rest.split_once('[')?;
+    let (bracket, _) = bracket.split_once(']')?;
+
+    // `[pc]` for old versions, `[cs_base/pc/...]` since then.
+    let fields: Vec<&str> = bracket.split('/').collect();
+    let pc = if fields.len() == 1 {
+        fields[0]
+    } else {
+        fields[1]
+    };
+
+    let pc = pc.trim();
+    u64::from_str_radix(pc.strip_prefix("0x").unwrap_or(pc), 16)
+        .ok()
+        .map(|pc| (cpu, pc))
+}
+
+/// Parse an instruction line of an `in_asm` log, returning its address and its size if
+/// the instruction bytes are printed.
+///
+/// Depending on the QEMU version and the disassembler, lines look like
+/// `0x00401136:  55                       pushq    %rbp` or `0x0000000000401136:  push %rbp`.
+/// The bytes column is padded with spaces up to the mnemonic: without at least two spaces
+/// after it, the line has no bytes column, whatever its mnemonic and operands look like.
+fn parse_in_asm(line: &str) -> Option<(u64, Option<u64>)> {
+    let (address, rest) = line.trim().split_once(':')?;
+    let address = u64::from_str_radix(address.strip_prefix("0x")?, 16).ok()?;
+
+    let size = rest
+        .trim_start()
+        .split_once("  ")
+        .and_then(|(bytes, _)| {
+            bytes.split(' ').try_fold(0, |size, token| {
+                (matches!(token.len(), 2 | 4 | 8) && token.chars().all(|c| c.is_ascii_hexdigit()))
+                    .then_some(size + token.len() as u64 / 2)
+            })
+        })
+        .filter(|size| *size > 0);
+
+    Some((address, size))
+}
+
+/// Parse a line of the `page` layout, like
+/// `0000004000000000-0000004000001000 0000000000001000 r-x`.
+fn parse_page(line: &str) -> Option<MapsEntry> {
+    let mut fields = line.split_whitespace();
+
+    let (start, end) = fields.next()?.split_once('-')?;
+    let start = u64::from_str_radix(start, 16).ok()?;
+    let end = u64::from_str_radix(end, 16).ok()?;
+    let _size = u64::from_str_radix(fields.next()?, 16).ok()?;
+    let prot = fields.next()?.as_bytes();
+
+    if prot.len() != 3 || fields.next().is_some() {
+        return None;
+    }
+
+    Some(MapsEntry {
+        range: start..end,
+        perms: MapsPerms {
+            read: prot[0] == b'r',
+            write: prot[1] == b'w',
+            execute: prot[2] == b'x',
+            shared: false,
+        },
+        file_offset: 0,
+        backing: MapsBacking::Anonymous,
+    })
+}
+
+impl QemuLogImporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read<R>(&mut self, reader: R) -> Result<(), Error>
+    where
+        R: BufRead,
+    {
+        for line in reader.lines() {
+            self.parse_line(&line?);
+        }
+        self.end_in_asm();
+        // executions in another log do not follow the last ones of this log.
+        self.last_pc.clear();
+
+        Ok(())
+    }
+
+    fn end_in_asm(&mut self) {
+        let Some(InAsmBlock {
+            start: Some(start),
+            last: Some((last, last_size)),
+        }) = self.in_asm.take()
+        else {
+            return;
+        };
+
+        // without instruction bytes, the size of the block is unknown.
+        let size = last_size
+            .and_then(|last_size| u32::try_from(last + last_size - start).ok())
+            .unwrap_or(0);
+
+        let block_size = self.sizes.entry(start).or_default();
+        *block_size = size.max(*block_size);
+    }
+
+    /// Parse a line of a QEMU log. Unknown lines are ignored.
+    pub fn parse_line(&mut self, line: &str) {
+        if let Some(in_asm) = &mut self.in_asm {
+            if let Some((address, size)) = parse_in_asm(line) {
+                in_asm.start.get_or_insert(address);
+                in_asm.last = Some((address, size));
+                return;
+            }
+
+            self.end_in_asm();
+        }
+
+        if line.starts_with("IN:") {
+            self.in_asm = Some(InAsmBlock::default());
+        } else if let Some((cpu, pc)) = parse_exec(line) {
+            *self.nb_taken.entry(pc).or_default() += 1;
+
+            if let Some(last_pc) = self.last_pc.insert(cpu, pc) {
+                *self.edges.entry((last_pc, pc)).or_default() += 1;
+            }
+        } else if line.split_whitespace().eq(["start", "end", "size", "prot"]) {
+            // a new layout is printed each time it changes.
+            self.page_layout = Some(ProcMaps::default());
+        } else if let Some(page_layout) = &mut self.page_layout
+            && let Some(entry) = parse_page(line)
+        {
+            page_layout.entries.push(entry);
+        }
+    }
+
+    /// Build the trace.
+    ///
+    /// Guest PCs are mapped to the modules of `maps`, or to the last page layout found in
+    /// the log. Without any of them, a single module without path covers every PC.
+    /// If the log has no `exec` item, the translated blocks are used with an unknown
+    /// `nb_taken`.
+    pub fn finish(self, maps: Option<&ProcMaps>, hdr: AlcovHeader) -> Result<Alcov, Error> {
+        let has_exec = !self.nb_taken.is_empty();
+
+        let mut pcs: Vec<u64> = if has_exec {
+            self.nb_taken.keys().copied().collect()
+        } else {
+            self.sizes.keys().copied().collect()
+        };
+        pcs.sort_unstable();
+
+        let modules = match maps.or(self.page_layout.as_ref()) {
+            Some(maps) => maps.modules(),
+            None if !pcs.is_empty() => {
+                let start = pcs[0] & !0xfff;
+                let end = pcs
+                    .iter()
+                    .map(|pc| pc + self.sizes.get(pc).copied().unwrap_or(0) as u64 + 1)
+                    .max()
+                    .unwrap()
+                    .next_multiple_of(0x1000);
+
+                vec![AlcovModule::new(
+                    start,
+                    None,
+                    vec![AlcovSegment::new(0..(end - start))],
+                )?]
+            }
+            None => Vec::new(),
+        };
+
+        let blocks: Vec<AddressBlock> = pcs
+            .iter()
+            .map(|pc| AddressBlock {
+                address: *pc,
+                size: self.sizes.get(pc).copied().unwrap_or(0),
+                nb_taken: self.nb_taken.get(pc).copied().unwrap_or(0),
+            })
+            .collect();
+
+        let edges: Option<HashMap<(usize, usize), u64>> = has_exec.then(|| {
+            let block_ids: HashMap<u64, usize> =
+                pcs.iter().enumerate().map(|(i, pc)| (*pc, i)).collect();
+
+            self.edges
+                .iter()
+                .map(|((src, dst), nb_taken)| ((block_ids[src], block_ids[dst]), *nb_taken))
+                .collect()
+        });
+
+        build_alcov(hdr, modules, &blocks, edges.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{AlcovBlock, AlcovDstBlockEdgeMetadata};
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    const LOG: &str = "\
+page layout changed following binary load
+start            end              size             prot
+0000000000400000-0000000000401000 0000000000001000 r--
+0000000000401000-0000000000402000 0000000000001000 r-x
+----------------
+IN: _start
+0x00401000:  31 ed                    xorl     %ebp, %ebp
+0x00401002:  eb 0c                    jmp      0x401010
+
+----------------
+IN:
+0x00401010:  c3                       retq
+
+Trace 0: 0x7f0000000100 [00000000/0000000000401000/00000000/ff020000] _start
+Trace 0: 0x7f0000000200 [00000000/0000000000401010/00000000/ff020000]
+Trace 0: 0x7f0000000100 [00000000/0000000000401000/00000000/ff020000] _start
+Trace 0: 0x7f0000000200 [00000000/0000000000401010/00000000/ff020000]
+";
+
+    #[test]
+    fn test_parse_in_asm() {
+        assert_eq!(
+            parse_in_asm("0x00401000:  31 ed                    xorl     %ebp, %ebp"),
+            Some((0x401000, Some(2)))
+        );
+        assert_eq!(
+            parse_in_asm("0x00010074:  e52db004  str      fp, [sp, #-4]!"),
+            Some((0x10074, Some(4)))
+        );
+        assert_eq!(
+            parse_in_asm("0x0000000000401136:  add $0xbeef, %eax"),
+            Some((0x401136, None))
+        );
+        assert_eq!(
+            parse_in_asm("0x0000000000401136:  dec  beef"),
+            Some((0x401136, None))
+        );
+        assert_eq!(parse_in_asm("IN: main"), None);
+    }
+
+    #[test]
+    fn test_qemu_log() {
+        let mut importer = QemuLogImporter::new();
+        importer.read(Cursor::new(LOG)).unwrap();
+        // a second log starting at another PC adds no edge from the end of the first one.
+        importer
+            .read(Cursor::new(
+                "Trace 0: 0x7f0000000200 [00000000/0000000000401010/00000000/ff020000]\n",
+            ))
+            .unwrap();
+
+        let alcov = importer
+            .finish(None, AlcovHeader::new(None::<PathBuf>, false))
+            .unwrap();
+
+        assert_eq!(alcov.modules.len(), 1);
+        assert_eq!(alcov.modules[0].base_address, 0x401000);
+        assert_eq!(
+            alcov.blocks,
+            vec![
+                AlcovBlock::new(0, 0, 0, 4, 2),
+                AlcovBlock::new(0, 0, 0x10, 1, 3),
+            ]
+        );
+
+        let edges = alcov.edges.unwrap();
+        assert_eq!(edges.nb_edges(), 2);
+        assert_eq!(
+            edges.adj_list[0].dst_modules[&1.into()],
+            AlcovDstBlockEdgeMetadata { nb_taken: 2 }
+        );
+    }
+}
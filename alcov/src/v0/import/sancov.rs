@@ -0,0 +1,296 @@
+//! LLVM SanitizerCoverage `.sancov` files.
+//!
+//! A `.sancov` file holds the list of PCs covered in a single module, as dumped by the
+//! sanitizer runtime. PCs are return addresses of the coverage callbacks, relative to the
+//! module's load bias (i.e. link-time addresses).
+//! When the binary has been built with `-fsanitize-coverage=pc-table`, the `__sancov_pcs`
+//! section gives the start of every instrumented block, which is used to recover blocks.
+
+use crate::v0::{Alcov, AlcovBlock, AlcovHeader, AlcovModule, ED, Error};
+use byteorder::ReadBytesExt;
+use object::{Object, ObjectSection, ObjectSymbol, RelocationTarget, SymbolKind};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+pub const SANCOV_MAGIC_64: u64 = 0xc0bfffffffffff64;
+pub const SANCOV_MAGIC_32: u64 = 0xc0bfffffffffff32;
+
+/// The covered PCs of a module, as found in a `.sancov` file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sancov {
+    pub pcs: Vec<u64>,
+}
+
+impl Sancov {
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let magic = reader.read_u64::<ED>()?;
+
+        let mut pcs_buf = Vec::new();
+        reader.read_to_end(&mut pcs_buf)?;
+
+        let pcs = match magic {
+            SANCOV_MAGIC_64 => pcs_buf
+                .chunks_exact(8)
+                .map(|pc| u64::from_le_bytes(pc.try_into().unwrap()))
+                .collect(),
+            SANCOV_MAGIC_32 => pcs_buf
+                .chunks_exact(4)
+                .map(|pc| u32::from_le_bytes(pc.try_into().unwrap()) as u64)
+                .collect(),
+            _ => return Err(Error::WrongMagic),
+        };
+
+        Ok(Self { pcs })
+    }
+}
+
+/// Whether a `.sancov` file holds the coverage of a binary.
+///
+/// The sanitizer runtime names the files `<module>.<pid>.sancov`, with the file name of
+/// the module: `libfoo.so.1.2.1234.sancov` matches `/usr/lib/libfoo.so.1.2`, but not
+/// `/usr/lib/libfoo.so`.
+pub fn is_sancov_of(sancov_path: &Path, binary: &Path) -> bool {
+    let (Some(file_name), Some(binary_name)) = (sancov_path.file_name(), binary.file_name()) else {
+        return false;
+    };
+
+    let Some(rest) = file_name
+        .as_encoded_bytes()
+        .strip_prefix(binary_name.as_encoded_bytes())
+    else {
+        return false;
+    };
+
+    match rest.strip_suffix(b".sancov") {
+        Some(b"") => true,
+        Some(pid) => pid
+            .strip_prefix(b".")
+            .is_some_and(|pid| !pid.is_empty() && pid.iter().all(u8::is_ascii_digit)),
+        None => false,
+    }
+}
+
+/// Block boundaries of a binary.
+#[derive(Debug, Clone, Default)]
+struct BinaryBlocks {
+    /// sorted block starts, from the `__sancov_pcs` section. empty if it is absent.
+    starts: Vec<u64>,
+    /// sorted function ranges, from the symbol table.
+    functions: Vec<(u64, u64)>,
+}
+
+impl BinaryBlocks {
+    fn from_elf(file: &object::File) -> Result<Self, Error> {
+        let mut functions: Vec<(u64, u64)> = file
+            .symbols()
+            .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.size() > 0)
+            .map(|symbol| (symbol.address(), symbol.address() + symbol.size()))
+            .collect();
+        functions.sort_unstable();
+
+        let mut starts: Vec<u64> = Vec::new();
+        if let Some(section) = file.section_by_name("__sancov_pcs") {
+            let table = section.data().map_err(|_| Error::MalformedBinary)?;
+            let ptr_size = if file.is_64() { 8 } else { 4 };
+
+            // in position-independent binaries, the table is filled by relative relocations.
+            let mut relocated: HashMap<u64, u64> = HashMap::new();
+            for (offset, relocation) in file.dynamic_relocations().into_iter().flatten() {
+                if relocation.target() == RelocationTarget::Absolute
+                    && !relocation.has_implicit_addend()
+                {
+                    relocated.insert(offset, relocation.addend() as u64);
+                }
+            }
+
+            // the table is made of (pc, flags) pairs.
+            for (i, entry) in table.chunks_exact(2 * ptr_size).enumerate() {
+                let address = section.address() + (i * 2 * ptr_size) as u64;
+
+                let pc = if let Some(pc) = relocated.get(&address) {
+                    *pc
+                } else {
+                    let mut pc = [0u8; 8];
+                    if file.is_little_endian() {
+                        pc[..ptr_size].copy_from_slice(&entry[..ptr_size]);
+                        u64::from_le_bytes(pc)
+                    } else {
+                        pc[8 - ptr_size..].copy_from_slice(&entry[..ptr_size]);
+                        u64::from_be_bytes(pc)
+                    }
+                };
+
+                starts.push(pc);
+            }
+
+            starts.sort_unstable();
+            starts.dedup();
+        }
+
+        Ok(Self { starts, functions })
+    }
+
+    fn function_of(&self, address: u64) -> Option<(u64, u64)> {
+        let idx = self
+            .functions
+            .partition_point(|(start, _)| *start <= address);
+
+        self.functions[..idx]
+            .iter()
+            .rev()
+            .find(|(_, end)| address < *end)
+            .copied()
+    }
+
+    /// Get the start and size of the block of a sancov PC.
+    ///
+    /// Without block information, the block is assumed to start at the previous instruction
+    /// with an unknown (0) size.
+    fn block_of(&self, pc: u64) -> (u64, u32) {
+        let address = pc.saturating_sub(1);
+        let unknown = (address, 0);
+
+        let idx = self.starts.partition_point(|start| *start <= address);
+        if idx == 0 {
+            return unknown;
+        }
+
+        let start = self.starts[idx - 1];
+        let mut end = self.starts.get(idx).copied();
+
+        if let Some((function_start, function_end)) = self.function_of(address) {
+            if start < function_start {
+                return unknown;
+            }
+
+            end = Some(end.map_or(function_end, |end| end.min(function_end)));
+        }
+
+        match end.map(|end| u32::try_from(end - start)) {
+            Some(Ok(size)) => (start, size),
+            _ => (start, 0),
+        }
+    }
+}
+
+/// Build an alcov trace from sancov files and their binaries.
+#[derive(Debug, Clone, Default)]
+pub struct SancovImporter {
+    modules: Vec<AlcovModule>,
+    binaries: Vec<BinaryBlocks>,
+    blocks: Vec<AlcovBlock>,
+    /// (module ID, offset from module base) to block ID.
+    block_ids: HashMap<(u16, u64), usize>,
+}
+
+impl SancovImporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the coverage of a binary.
+    ///
+    /// Modules have their link address as base address. The same binary can be added
+    /// multiple times (e.g. for multiple processes), blocks are only added once.
+    pub fn add(&mut self, binary: &Path, sancov: &Sancov) -> Result<(), Error> {
+        let module_id = if let Some(module_id) = self
+            .modules
+            .iter()
+            .position(|module| module.path.as_deref() == Some(binary))
+        {
+            module_id
+        } else {
+            let data = fs::read(binary)?;
+            let file = object::File::parse(&*data).map_err(|_| Error::MalformedBinary)?;
+            let image_base = AlcovModule::elf_image_base(&file)?;
+
+            self.modules
+                .push(AlcovModule::from_elf_data(binary, &data, image_base)?);
+            self.binaries.push(BinaryBlocks::from_elf(&file)?);
+            self.modules.len() - 1
+        };
+
+        let module = &self.modules[module_id];
+        let binary_blocks = &self.binaries[module_id];
+        let module_id = u16::try_from(module_id)?;
+
+        for pc in &sancov.pcs {
+            let (start, size) = binary_blocks.block_of(*pc);
+
+            let Some(offset) = start.checked_sub(module.base_address) else {
+                continue;
+            };
+
+            let Some((segment_id, segment_offset)) = module.locate(offset) else {
+                continue;
+            };
+
+            if self.block_ids.contains_key(&(module_id, offset)) {
+                continue;
+            }
+
+            self.block_ids
+                .insert((module_id, offset), self.blocks.len());
+            self.blocks.push(AlcovBlock::new(
+                module_id,
+                segment_id,
+                segment_offset,
+                size,
+                0,
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self, hdr: AlcovHeader) -> Alcov {
+        Alcov::new(hdr, self.modules, self.blocks, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_sancov_blocks() {
+        let mut file: Vec<u8> = SANCOV_MAGIC_32.to_le_bytes().to_vec();
+        file.extend_from_slice(&0x1105u32.to_le_bytes());
+        file.extend_from_slice(&0x1212u32.to_le_bytes());
+        file.extend_from_slice(&0x2005u32.to_le_bytes());
+
+        let sancov = Sancov::read(&mut Cursor::new(file)).unwrap();
+        assert_eq!(sancov.pcs, vec![0x1105, 0x1212, 0x2005]);
+
+        let binary = BinaryBlocks {
+            starts: vec![0x1100, 0x1120, 0x1200, 0x2010],
+            functions: vec![(0x1100, 0x1180), (0x1200, 0x1240), (0x2000, 0x2040)],
+        };
+
+        assert_eq!(binary.block_of(0x1105), (0x1100, 0x20));
+        assert_eq!(binary.block_of(0x1212), (0x1200, 0x40));
+        // the closest block start is in another function.
+        assert_eq!(binary.block_of(0x2005), (0x2004, 0));
+    }
+
+    #[test]
+    fn test_sancov_of() {
+        let libfoo = Path::new("/usr/lib/libfoo.so");
+        assert!(is_sancov_of(Path::new("out/libfoo.so.1234.sancov"), libfoo));
+        assert!(is_sancov_of(Path::new("libfoo.so.sancov"), libfoo));
+        assert!(!is_sancov_of(Path::new("libfoo.so.1.1234.sancov"), libfoo));
+        assert!(!is_sancov_of(Path::new("libfoo.so.1234"), libfoo));
+        assert!(!is_sancov_of(Path::new("libbar.so.1234.sancov"), libfoo));
+        assert!(!is_sancov_of(Path::new("libfoo.1234.sancov"), libfoo));
+
+        let libfoo_1 = Path::new("/usr/lib/libfoo.so.1");
+        assert!(is_sancov_of(Path::new("libfoo.so.1.1234.sancov"), libfoo_1));
+        assert!(!is_sancov_of(Path::new("libfoo.so.1234.sancov"), libfoo_1));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::v0::{AlcovCodec, Error};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Content of the input given to the traced program, embedded in the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlcovInput {
+    pub data: Vec<u8>,
+    /// codec of the input chunk.
+    pub codec: AlcovCodec,
+}
+
+impl AlcovInput {
+    /// The input is compressed with zstd.
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            codec: AlcovCodec::Zstd,
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        Ok(Self::new(fs::read(path)?))
+    }
+
+    pub fn sha256(&self) -> [u8; 32] {
+        Sha256::digest(&self.data).into()
+    }
+
+    /// Write the input chunk, before compression.
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        writer.write_all(&self.sha256())?;
+        writer.write_all(&self.data)?;
+
+        Ok(())
+    }
+
+    /// Read a decompressed input chunk, checking the hash of the input.
+    pub fn read(mut input_buf: Vec<u8>, codec: AlcovCodec) -> Result<Self, Error> {
+        if input_buf.len() < 32 {
+            return Err(Error::InputHashMismatch);
+        }
+
+        let data = input_buf.split_off(32);
+        let input = Self { data, codec };
+        if input.sha256()[..] != input_buf[..] {
+            return Err(Error::InputHashMismatch);
+        }
+
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{Alcov, AlcovBlock, AlcovHeader, AlcovModule, AlcovSegment};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_input() {
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap()];
+        let blocks = vec![AlcovBlock::new(0, 0, 0x10, 4, 1)];
+
+        let mut hdr = AlcovHeader::new(Some("crash-1234"), false);
+        hdr.input = Some(AlcovInput::new(b"GET /../../etc/passwd".to_vec()));
+        let mut alcov = Alcov::new(hdr, modules, blocks, None);
+        assert!(alcov.should_compress());
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+
+        alcov.hdr.set_codec(AlcovCodec::None);
+        alcov.hdr.checksum = None;
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+
+        let input_offset = out_buf
+            .windows(6)
+            .position(|window| window == b"passwd")
+            .unwrap();
+        out_buf[input_offset] = b'P';
+        assert!(matches!(
+            Alcov::read(&mut Cursor::new(out_buf)),
+            Err(Error::InputHashMismatch)
+        ));
+    }
+}
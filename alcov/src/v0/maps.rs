@@ -0,0 +1,301 @@
+use crate::v0::{AlcovModule, AlcovPerms, AlcovSegment, Error};
+use std::fs;
+use std::io::Read;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str;
+
+/// Permissions of a mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MapsPerms {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    pub shared: bool,
+}
+
+/// What is backing a mapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapsBacking {
+    /// A file on disk, identified by its path and inode.
+    File { path: PathBuf, inode: u64 },
+    /// A named region without file, like `[vdso]`, `[heap]` or `[anon:name]`.
+    Named(String),
+    /// An anonymous region without any name.
+    Anonymous,
+}
+
+/// A line of a `/proc/<pid>/maps` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapsEntry {
+    pub range: Range<u64>,
+    pub perms: MapsPerms,
+    pub file_offset: u64,
+    pub backing: MapsBacking,
+}
+
+/// A snapshot of the memory mappings of a process, as found in `/proc/<pid>/maps`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProcMaps {
+    pub entries: Vec<MapsEntry>,
+}
+
+impl MapsPerms {
+    fn parse(perms: &[u8]) -> Option<Self> {
+        if perms.len() != 4 {
+            return None;
+        }
+
+        Some(Self {
+            read: perms[0] == b'r',
+            write: perms[1] == b'w',
+            execute: perms[2] == b'x',
+            shared: perms[3] == b's',
+        })
+    }
+}
+
+/// Path from raw bytes. Outside of Unix, paths must be valid UTF-8.
+fn bytes_path(bytes: &[u8]) -> Option<PathBuf> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+    }
+
+    #[cfg(not(unix))]
+    {
+        str::from_utf8(bytes).ok().map(PathBuf::from)
+    }
+}
+
+impl From<MapsPerms> for AlcovPerms {
+    fn from(perms: MapsPerms) -> Self {
+        let mut alcov_perms = AlcovPerms::empty();
+        alcov_perms.set(AlcovPerms::Read, perms.read);
+        alcov_perms.set(AlcovPerms::Write, perms.write);
+        alcov_perms.set(AlcovPerms::Execute, perms.execute);
+        alcov_perms
+    }
+}
+
+impl MapsEntry {
+    /// Parse a single line of a maps file.
+    ///
+    /// The format is `start-end perms offset dev inode [pathname]`. The pathname may
+    /// contain spaces, and is suffixed by ` (deleted)` when the file has been removed.
+    /// Paths are kept as raw bytes, as they do not have to be valid UTF-8.
+    pub fn parse(line: &[u8]) -> Option<Self> {
+        let mut fields = line.splitn(6, |&c| c == b' ');
+
+        let (start, end) = str::from_utf8(fields.next()?).ok()?.split_once('-')?;
+        let start = u64::from_str_radix(start, 16).ok()?;
+        let end = u64::from_str_radix(end, 16).ok()?;
+        let perms = MapsPerms::parse(fields.next()?)?;
+        let file_offset = u64::from_str_radix(str::from_utf8(fields.next()?).ok()?, 16).ok()?;
+        let _dev = fields.next()?;
+        let inode: u64 = str::from_utf8(fields.next()?).ok()?.parse().ok()?;
+        let pathname = fields.next().unwrap_or_default().trim_ascii_start();
+
+        let backing = if pathname.is_empty() {
+            MapsBacking::Anonymous
+        } else if inode != 0 && pathname.starts_with(b"/") {
+            let path = pathname.strip_suffix(b" (deleted)").unwrap_or(pathname);
+            MapsBacking::File {
+                path: bytes_path(path)?,
+                inode,
+            }
+        } else {
+            MapsBacking::Named(String::from_utf8_lossy(pathname).into_owned())
+        };
+
+        Some(Self {
+            range: start..end,
+            perms,
+            file_offset,
+            backing,
+        })
+    }
+
+    /// The path to use for a module backed by this mapping.
+    pub fn module_path(&self) -> Option<PathBuf> {
+        match &self.backing {
+            MapsBacking::File { path, .. } => Some(path.clone()),
+            MapsBacking::Named(name) => Some(PathBuf::from(name)),
+            MapsBacking::Anonymous => None,
+        }
+    }
+}
+
+impl ProcMaps {
+    pub fn parse(maps: &[u8]) -> Result<Self, Error> {
+        let mut entries: Vec<MapsEntry> = Vec::new();
+
+        for (i, line) in maps.split(|&c| c == b'\n').enumerate() {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            entries.push(MapsEntry::parse(line).ok_or(Error::MalformedMaps { line: i + 1 })?);
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let mut maps: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut maps)?;
+
+        Self::parse(&maps)
+    }
+
+    /// Snapshot the mappings of a live process.
+    pub fn from_pid(pid: u32) -> Result<Self, Error> {
+        Self::parse(&fs::read(format!("/proc/{pid}/maps"))?)
+    }
+
+    /// Group the mappings in modules.
+    ///
+    /// Every mapping of the same file (same path and inode) ends up in a single module,
+    /// with one segment per mapping relative to the lowest mapped address.
+    /// Regions without file only become modules if they are executable (JIT code,
+    /// `[vdso]`, ...): named regions are grouped by name, and each anonymous region gets
+    /// its own module without path.
+    pub fn modules(&self) -> Vec<AlcovModule> {
+        let mut groups: Vec<Vec<&MapsEntry>> = Vec::new();
+
+        for entry in &self.entries {
+            match &entry.backing {
+                MapsBacking::File { .. } => {}
+                MapsBacking::Named(_) | MapsBacking::Anonymous if entry.perms.execute => {}
+                _ => continue,
+            }
+
+            let group = if entry.backing == MapsBacking::Anonymous {
+                None
+            } else {
+                groups
+                    .iter_mut()
+                    .find(|group| group[0].backing == entry.backing)
+            };
+
+            if let Some(group) = group {
+                group.push(entry);
+            } else {
+                groups.push(vec![entry]);
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|group| {
+                let first = group[0];
+                let base_address = group.iter().map(|entry| entry.range.start).min().unwrap();
+                let segments = group
+                    .iter()
+                    .map(|entry| {
+                        let mut segment = AlcovSegment::new(
+                            (entry.range.start - base_address)..(entry.range.end - base_address),
+                        );
+                        segment.perms = entry.perms.into();
+                        if let MapsBacking::File { .. } = entry.backing {
+                            segment.file_offset = Some(entry.file_offset);
+                        }
+                        segment
+                    })
+                    .collect();
+
+                // there is at least one segment per group.
+                AlcovModule::new(base_address, first.module_path(), segments).unwrap()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = "\
+55d0c4a00000-55d0c4a02000 r--p 00000000 fd:01 1835017                    /usr/bin/cat
+55d0c4a02000-55d0c4a07000 r-xp 00002000 fd:01 1835017                    /usr/bin/cat
+55d0c4a07000-55d0c4a0a000 r--p 00007000 fd:01 1835017                    /usr/bin/cat
+55d0c5c1a000-55d0c5c3b000 rw-p 00000000 00:00 0                          [heap]
+7f3a10000000-7f3a10001000 rwxp 00000000 00:00 0
+7f3a2c400000-7f3a2c426000 r--p 00000000 fd:01 1837228                    /home/user/my libs/libc.so.6 (deleted)
+7f3a2c426000-7f3a2c57c000 r-xp 00026000 fd:01 1837228                    /home/user/my libs/libc.so.6 (deleted)
+7ffd1c9f3000-7ffd1c9f5000 r-xp 00000000 00:00 0                          [vdso]
+";
+
+    #[test]
+    fn test_maps_modules() {
+        let maps = ProcMaps::parse(MAPS.as_bytes()).unwrap();
+        assert_eq!(maps.entries.len(), 8);
+
+        let modules = maps.modules();
+        assert_eq!(modules.len(), 4);
+
+        assert_eq!(modules[0].path, Some(PathBuf::from("/usr/bin/cat")));
+        assert_eq!(modules[0].base_address, 0x55d0c4a00000);
+        assert_eq!(
+            modules[0]
+                .segments
+                .iter()
+                .map(|segment| (segment.module_range.clone(), segment.file_offset))
+                .collect::<Vec<_>>(),
+            vec![
+                (0..0x2000, Some(0)),
+                (0x2000..0x7000, Some(0x2000)),
+                (0x7000..0xa000, Some(0x7000)),
+            ]
+        );
+        assert_eq!(
+            modules[0].segments[1].perms,
+            AlcovPerms::Read | AlcovPerms::Execute
+        );
+
+        assert_eq!(modules[1].path, None);
+        assert_eq!(modules[1].segments[0].file_offset, None);
+        assert_eq!(
+            modules[2].path,
+            Some(PathBuf::from("/home/user/my libs/libc.so.6"))
+        );
+        assert_eq!(modules[2].segments.len(), 2);
+        assert_eq!(modules[3].path, Some(PathBuf::from("[vdso]")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_maps_raw_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut maps_buf = b"\
+7f3a2c400000-7f3a2c426000 r-xp 00000000 fd:01 1837228                    /tmp/caf\xe9/lib.so
+7f3a2c500000-7f3a2c501000 r-xp 00000000 fd:01 1837229                    /tmp/a b (deleted) (deleted)
+7f3a2c600000-7f3a2c601000 r-xp 00000000 00:00 0                          [anon:jit\xff]
+"
+        .to_vec();
+        let maps = ProcMaps::read(&mut maps_buf.as_slice()).unwrap();
+        assert_eq!(maps.entries.len(), 3);
+
+        let modules = maps.modules();
+        assert_eq!(
+            modules[0]
+                .path
+                .as_deref()
+                .map(|path| path.as_os_str().as_bytes()),
+            Some(&b"/tmp/caf\xe9/lib.so"[..])
+        );
+        assert_eq!(modules[1].path, Some(PathBuf::from("/tmp/a b (deleted)")));
+        assert_eq!(modules[2].path, Some(PathBuf::from("[anon:jit\u{fffd}]")));
+
+        maps_buf.extend_from_slice(b"7f3a2c700000 r-xp\n");
+        assert!(matches!(
+            ProcMaps::parse(&maps_buf),
+            Err(Error::MalformedMaps { line: 4 })
+        ));
+    }
+}
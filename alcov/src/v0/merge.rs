@@ -0,0 +1,286 @@
+use crate::v0::{Alcov, AlcovBlock, AlcovEdges, AlcovModule, AlcovThread, Error};
+use std::collections::HashMap;
+
+impl Alcov {
+    /// Add the coverage of another trace of the same program.
+    ///
+    /// Modules are matched by path, and blocks by their offset in their module. Modules
+    /// without a path, or only found in `other`, are added. Hit counts are added up,
+    /// saturating at `u64::MAX`, and edges are kept only if both traces have edges. Threads with the same id are merged.
+    /// Metadata is combined with [`crate::v0::AlcovHeader::merge_metadata`].
+    ///
+    /// If two modules with the same path are backed by different files according to their
+    /// identities, an error is returned and nothing is modified. Identities of a kind only
+    /// `other` has are added once an identity of another kind shows that both modules are
+    /// backed by the same file.
+    pub fn merge(&mut self, other: &Alcov) -> Result<(), Error> {
+        let mut modules = self.modules.clone();
+
+        // module of each module of `other`.
+        let mut module_ids: Vec<u16> = Vec::new();
+        for module in &other.modules {
+            let found = module.path.as_ref().and_then(|path| {
+                modules
+                    .iter()
+                    .position(|merged| merged.path.as_ref() == Some(path))
+            });
+
+            let module_id = match found {
+                Some(module_id) => {
+                    let merged = &mut modules[module_id];
+                    let same_file = merged.same_file(module);
+                    if same_file == Some(false) {
+                        return Err(Error::ModuleIdentityMismatch(module.path.clone().unwrap()));
+                    }
+
+                    // identities, architecture and code only known by `other` are kept.
+                    if same_file == Some(true) {
+                        for identity in &module.identities {
+                            if !merged
+                                .identities
+                                .iter()
+                                .any(|merged_identity| merged_identity.kind() == identity.kind())
+                            {
+                                merged.identities.push(identity.clone());
+                            }
+                        }
+                    }
+                    if merged.arch.is_none() {
+                        merged.arch = module.arch;
+                    }
+                    if merged.code.is_none() {
+                        merged.code = module.code.clone();
+                    }
+
+                    module_id
+                }
+                None => {
+                    modules.push(module.clone());
+                    modules.len() - 1
+                }
+            };
+
+            module_ids.push(u16::try_from(module_id)?);
+        }
+
+        let mut blocks = self.blocks.clone();
+        let mut block_ids: HashMap<(u16, u64), usize> = HashMap::new();
+        for (block_id, block) in blocks.iter().enumerate() {
+            block_ids.insert(module_offset(&modules, block, block_id)?, block_id);
+        }
+
+        // block of each block of `other`.
+        let mut other_block_ids: Vec<usize> = Vec::new();
+        for (block_id, block) in other.blocks.iter().enumerate() {
+            let (_, offset) = module_offset(&other.modules, block, block_id)?;
+            let module_id = module_ids[block.module_id as usize];
+
+            let merged_id = match block_ids.get(&(module_id, offset)) {
+                Some(&merged_id) => {
+                    let merged = &mut blocks[merged_id];
+                    merged.nb_taken = merged.nb_taken.saturating_add(block.nb_taken);
+                    merged.mode = merged.mode.or(block.mode);
+                    merged.nb_instructions = merged.nb_instructions.or(block.nb_instructions);
+                    merged_id
+                }
+                None => {
+                    let (segment_id, segment_offset) = modules[module_id as usize]
+                        .locate(offset)
+                        .ok_or(Error::BlockOutOfModule {
+                        block_id: block_id as u64,
+                    })?;
+
+                    blocks.push(AlcovBlock {
+                        module_id,
+                        segment_id,
+                        segment_offset,
+                        ..block.clone()
+                    });
+                    block_ids.insert((module_id, offset), blocks.len() - 1);
+                    blocks.len() - 1
+                }
+            };
+
+            other_block_ids.push(merged_id);
+        }
+
+        let edges = match (&self.edges, &other.edges) {
+            (Some(edges), Some(other_edges)) => {
+                let mut edges: AlcovEdges = edges.clone();
+                edges.adj_list.resize(blocks.len(), Default::default());
+
+                for (src, block_edges) in other_edges.adj_list.iter().enumerate() {
+                    let src = *other_block_ids.get(src).ok_or(Error::EdgeWithoutBlock {
+                        block_id: src as u64,
+                    })?;
+
+                    for (dst_edge, dst_edge_md) in &block_edges.dst_modules {
+                        let dst = *other_block_ids.get(dst_edge.dst_block_id as usize).ok_or(
+                            Error::EdgeWithoutBlock {
+                                block_id: dst_edge.dst_block_id,
+                            },
+                        )?;
+
+                        edges.adj_list[src]
+                            .dst_modules
+                            .entry((dst as u64).into())
+                            .and_modify(|md| {
+                                md.nb_taken = md.nb_taken.saturating_add(dst_edge_md.nb_taken)
+                            })
+                            .or_insert_with(|| dst_edge_md.clone());
+                    }
+                }
+
+                Some(edges)
+            }
+            _ => None,
+        };
+
+        let mut threads = self.threads.clone();
+        for other_thread in &other.threads {
+            let thread_id = match threads
+                .iter()
+                .position(|thread| thread.tid == other_thread.tid)
+            {
+                Some(thread_id) => thread_id,
+                None => {
+                    threads.push(AlcovThread::new(other_thread.tid, None));
+                    threads.len() - 1
+                }
+            };
+            let thread = &mut threads[thread_id];
+            if thread.name.is_none() {
+                thread.name = other_thread.name.clone();
+            }
+
+            let block_id = |block_id: u64| {
+                other_block_ids
+                    .get(block_id as usize)
+                    .map(|&merged_id| merged_id as u64)
+                    .ok_or(Error::ThreadWithoutBlock {
+                        tid: other_thread.tid,
+                        block_id,
+                    })
+            };
+            for (&block, &nb_taken) in &other_thread.blocks {
+                thread.add_block(block_id(block)?, nb_taken);
+            }
+            for (&(src, dst), &nb_taken) in &other_thread.edges {
+                thread.add_edge(block_id(src)?, block_id(dst)?, nb_taken);
+            }
+        }
+        if edges.is_none() {
+            threads.iter_mut().for_each(|thread| thread.edges.clear());
+        }
+
+        if self.hdr.input_path != other.hdr.input_path {
+            self.hdr.input_path = None;
+        }
+        let input_data = |alcov: &Alcov| alcov.hdr.input.as_ref().map(|input| input.data.clone());
+        if input_data(self) != input_data(other) {
+            self.hdr.input = None;
+        }
+        self.hdr.merge_metadata(&other.hdr);
+        self.modules = modules;
+        self.blocks = blocks;
+        self.edges = edges;
+        self.threads = threads;
+
+        Ok(())
+    }
+}
+
+/// Module and offset in that module of a block.
+fn module_offset(
+    modules: &[AlcovModule],
+    block: &AlcovBlock,
+    block_id: usize,
+) -> Result<(u16, u64), Error> {
+    modules
+        .get(block.module_id as usize)
+        .and_then(|module| module.module_offset(block.segment_id, block.segment_offset))
+        .map(|offset| (block.module_id, offset))
+        .ok_or(Error::BlockOutOfModule {
+            block_id: block_id as u64,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{AlcovHeader, AlcovModuleIdentity, AlcovSegment};
+    use std::path::PathBuf;
+
+    fn trace(base_address: u64, build_id: u8, blocks: &[(u64, u64)], edge: (u64, u64)) -> Alcov {
+        let mut module = AlcovModule::new(
+            base_address,
+            Some(PathBuf::from("/bin/a")),
+            vec![
+                AlcovSegment::new(0..0x1000),
+                AlcovSegment::new(0x1000..0x2000),
+            ],
+        )
+        .unwrap();
+        module.identities = vec![AlcovModuleIdentity::GnuBuildId(vec![build_id])];
+
+        let blocks: Vec<AlcovBlock> = blocks
+            .iter()
+            .map(|&(offset, nb_taken)| {
+                let (segment_id, segment_offset) = module.locate(offset).unwrap();
+                AlcovBlock::new(0, segment_id, segment_offset, 4, nb_taken)
+            })
+            .collect();
+        let mut edges = AlcovEdges::new();
+        edges.add(&blocks, edge.0, edge.1).unwrap();
+
+        Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, false),
+            vec![module],
+            blocks,
+            Some(edges),
+        )
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut alcov = trace(0x1000, 1, &[(0x10, 1), (0x1010, 2)], (0, 1));
+        let other = trace(0x5000, 1, &[(0x1010, 3), (0x20, 1)], (0, 1));
+
+        alcov.merge(&other).unwrap();
+        assert_eq!(alcov.modules.len(), 1);
+        assert_eq!(
+            alcov
+                .blocks
+                .iter()
+                .map(|block| (block.segment_id, block.segment_offset, block.nb_taken))
+                .collect::<Vec<_>>(),
+            vec![(0, 0x10, 1), (1, 0x10, 5), (0, 0x20, 1)]
+        );
+
+        let edges = alcov.edges.as_ref().unwrap();
+        assert_eq!(edges.nb_edges(), 2);
+        assert_eq!(edges.adj_list[1].dst_modules[&2.into()].nb_taken, 1);
+
+        let merged = alcov.clone();
+        let other = trace(0x1000, 2, &[(0x10, 1)], (0, 0));
+        assert!(matches!(
+            alcov.merge(&other),
+            Err(Error::ModuleIdentityMismatch(_))
+        ));
+        assert_eq!(alcov, merged);
+
+        // identities of another kind need a matching identity to be kept.
+        let sha256 = AlcovModuleIdentity::Sha256([0; 32]);
+        let mut other = trace(0x1000, 1, &[(0x10, u64::MAX)], (0, 0));
+        other.modules[0].identities = vec![sha256.clone()];
+        alcov.merge(&other).unwrap();
+        assert_eq!(alcov.modules[0].identities.len(), 1);
+        assert_eq!(alcov.blocks[0].nb_taken, u64::MAX);
+
+        other.modules[0]
+            .identities
+            .push(AlcovModuleIdentity::GnuBuildId(vec![1]));
+        alcov.merge(&other).unwrap();
+        assert_eq!(alcov.modules[0].identities[1], sha256);
+    }
+}
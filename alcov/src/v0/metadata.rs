@@ -0,0 +1,351 @@
+use crate::v0::{AlcovHeader, ED, Error, bindings};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Well-known metadata keys.
+pub mod keys {
+    /// [`super::AlcovValue::String`]: name of the tool that produced the trace.
+    pub const TRACER: &str = "tracer";
+    /// [`super::AlcovValue::String`]: version of the tool that produced the trace.
+    pub const TRACER_VERSION: &str = "tracer_version";
+    /// [`super::AlcovValue::Strings`]: command line of the traced program.
+    pub const COMMAND: &str = "command";
+    /// [`super::AlcovValue::Strings`]: environment of the traced program, as `NAME=value`.
+    pub const ENVIRONMENT: &str = "environment";
+    /// [`super::AlcovValue::String`]: name of the machine the program was traced on.
+    pub const HOSTNAME: &str = "hostname";
+    /// [`super::AlcovValue::Timestamp`]: start of the trace.
+    pub const START_TIME: &str = "start_time";
+    /// [`super::AlcovValue::Timestamp`]: end of the trace.
+    pub const END_TIME: &str = "end_time";
+    /// [`super::AlcovValue::String`]: fuzzing campaign the trace belongs to.
+    pub const CAMPAIGN_ID: &str = "campaign_id";
+    /// [`super::AlcovValue::Int`]: exit status of the traced program, if it exited.
+    pub const EXIT_STATUS: &str = "exit_status";
+    /// [`super::AlcovValue::Int`]: signal that killed the traced program, if any.
+    pub const EXIT_SIGNAL: &str = "exit_signal";
+    /// [`super::AlcovValue::UInt`]: number of traces merged in this one.
+    pub const MERGED_TRACES: &str = "merged_traces";
+}
+
+/// Value of a metadata entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AlcovValue {
+    String(String),
+    Int(i64),
+    UInt(u64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    /// nanoseconds since the UNIX epoch.
+    Timestamp(u64),
+    Strings(Vec<String>),
+}
+
+impl AlcovValue {
+    pub fn kind(&self) -> u8 {
+        let kind = match self {
+            Self::String(_) => bindings::ALCOV_VALUE_STRING,
+            Self::Int(_) => bindings::ALCOV_VALUE_INT,
+            Self::UInt(_) => bindings::ALCOV_VALUE_UINT,
+            Self::Bool(_) => bindings::ALCOV_VALUE_BOOL,
+            Self::Bytes(_) => bindings::ALCOV_VALUE_BYTES,
+            Self::Timestamp(_) => bindings::ALCOV_VALUE_TIMESTAMP,
+            Self::Strings(_) => bindings::ALCOV_VALUE_STRINGS,
+        };
+
+        kind as u8
+    }
+
+    /// Timestamp of a point in time. Times before the UNIX epoch are clamped to it.
+    pub fn timestamp(time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        Self::Timestamp(u64::try_from(since_epoch.as_nanos()).unwrap_or(u64::MAX))
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        match self {
+            Self::String(string) => string.as_bytes().to_vec(),
+            Self::Int(int) => int.to_le_bytes().to_vec(),
+            Self::UInt(uint) | Self::Timestamp(uint) => uint.to_le_bytes().to_vec(),
+            Self::Bool(boolean) => vec![*boolean as u8],
+            Self::Bytes(bytes) => bytes.clone(),
+            Self::Strings(strings) => strings
+                .iter()
+                .flat_map(|string| string.bytes().chain([0]))
+                .collect(),
+        }
+    }
+
+    pub fn from_data(kind: u8, data: Vec<u8>) -> Result<Self, Error> {
+        let string = |data: Vec<u8>| String::from_utf8(data).map_err(|_| Error::MalformedMetadata);
+        let int = |data: &[u8]| -> Result<[u8; 8], Error> {
+            data.try_into().map_err(|_| Error::MalformedMetadata)
+        };
+
+        match kind as u32 {
+            bindings::ALCOV_VALUE_STRING => Ok(Self::String(string(data)?)),
+            bindings::ALCOV_VALUE_INT => Ok(Self::Int(i64::from_le_bytes(int(&data)?))),
+            bindings::ALCOV_VALUE_UINT => Ok(Self::UInt(u64::from_le_bytes(int(&data)?))),
+            bindings::ALCOV_VALUE_BOOL => match data[..] {
+                [boolean] if boolean <= 1 => Ok(Self::Bool(boolean == 1)),
+                _ => Err(Error::MalformedMetadata),
+            },
+            bindings::ALCOV_VALUE_BYTES => Ok(Self::Bytes(data)),
+            bindings::ALCOV_VALUE_TIMESTAMP => Ok(Self::Timestamp(u64::from_le_bytes(int(&data)?))),
+            bindings::ALCOV_VALUE_STRINGS => {
+                let Some(data) = data.strip_suffix(&[0]) else {
+                    return if data.is_empty() {
+                        Ok(Self::Strings(Vec::new()))
+                    } else {
+                        Err(Error::MalformedMetadata)
+                    };
+                };
+
+                Ok(Self::Strings(
+                    data.split(|&b| b == 0)
+                        .map(|string| string.to_vec())
+                        .map(string)
+                        .collect::<Result<Vec<String>, Error>>()?,
+                ))
+            }
+            _ => Err(Error::WrongMetadataType(kind)),
+        }
+    }
+}
+
+impl AlcovHeader {
+    /// Add a metadata entry, replacing the previous value of the key if any.
+    pub fn set_metadata<K>(&mut self, key: K, value: AlcovValue)
+    where
+        K: Into<String>,
+    {
+        self.metadata.insert(key.into(), value);
+    }
+
+    pub fn get_metadata(&self, key: &str) -> Option<&AlcovValue> {
+        self.metadata.get(key)
+    }
+
+    /// Combine the metadata of two traces merged together.
+    ///
+    /// Entries with the same value in both traces are kept, along with the earliest start
+    /// time and the latest end time. Other entries only describe some of the traces, and
+    /// are removed. The number of merged traces is counted in [`keys::MERGED_TRACES`].
+    pub fn merge_metadata(&mut self, other: &AlcovHeader) {
+        let nb_traces = |hdr: &AlcovHeader| match hdr.get_metadata(keys::MERGED_TRACES) {
+            Some(AlcovValue::UInt(nb_traces)) => *nb_traces,
+            _ => 1,
+        };
+        let nb_traces = nb_traces(self) + nb_traces(other);
+
+        let mut metadata: BTreeMap<String, AlcovValue> = BTreeMap::new();
+        for (key, value) in &self.metadata {
+            let merged = match (key.as_str(), value, other.get_metadata(key)) {
+                (_, _, Some(other_value)) if other_value == value => value.clone(),
+                (
+                    keys::START_TIME,
+                    AlcovValue::Timestamp(time),
+                    Some(AlcovValue::Timestamp(other_time)),
+                ) => AlcovValue::Timestamp(*time.min(other_time)),
+                (
+                    keys::END_TIME,
+                    AlcovValue::Timestamp(time),
+                    Some(AlcovValue::Timestamp(other_time)),
+                ) => AlcovValue::Timestamp(*time.max(other_time)),
+                _ => continue,
+            };
+
+            metadata.insert(key.clone(), merged);
+        }
+
+        metadata.insert(keys::MERGED_TRACES.to_string(), AlcovValue::UInt(nb_traces));
+        self.metadata = metadata;
+    }
+}
+
+/// Write the metadata chunk.
+pub fn write_metadata<W>(
+    writer: &mut W,
+    metadata: &BTreeMap<String, AlcovValue>,
+) -> Result<(), Error>
+where
+    W: Write,
+{
+    for (key, value) in metadata {
+        let data = value.data();
+
+        writer.write_u16::<ED>(u16::try_from(key.len())?)?;
+        writer.write_u8(value.kind())?;
+        writer.write_u32::<ED>(u32::try_from(data.len())?)?;
+        writer.write_all(key.as_bytes())?;
+        writer.write_all(&data)?;
+    }
+
+    Ok(())
+}
+
+/// Read the metadata chunk.
+pub fn read_metadata(metadata_buf: &[u8]) -> Result<BTreeMap<String, AlcovValue>, Error> {
+    let mut metadata_rdr = Cursor::new(metadata_buf);
+    let mut metadata: BTreeMap<String, AlcovValue> = BTreeMap::new();
+
+    while metadata_rdr.position() < metadata_buf.len() as u64 {
+        let key_size = metadata_rdr.read_u16::<ED>()?;
+        let kind = metadata_rdr.read_u8()?;
+        let value_size = metadata_rdr.read_u32::<ED>()?;
+
+        // the sizes are checked before allocating, as they may be corrupted.
+        let remaining = metadata_buf.len() as u64 - metadata_rdr.position();
+        if key_size as u64 + value_size as u64 > remaining {
+            return Err(Error::MalformedMetadata);
+        }
+
+        let mut key = vec![0u8; key_size as usize];
+        metadata_rdr.read_exact(&mut key)?;
+        let key = String::from_utf8(key).map_err(|_| Error::MalformedMetadata)?;
+
+        let mut data = vec![0u8; value_size as usize];
+        metadata_rdr.read_exact(&mut data)?;
+
+        metadata.insert(key, AlcovValue::from_data(kind, data)?);
+    }
+
+    Ok(metadata)
+}
+
+/// Civil date (year, month, day) of a number of days since the UNIX epoch.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+impl Display for AlcovValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(string) => write!(f, "{:?}", string),
+            Self::Int(int) => write!(f, "{}", int),
+            Self::UInt(uint) => write!(f, "{}", uint),
+            Self::Bool(boolean) => write!(f, "{}", boolean),
+            Self::Bytes(bytes) => bytes.iter().try_for_each(|b| write!(f, "{b:02x}")),
+            Self::Timestamp(timestamp) => {
+                let secs = (timestamp / 1_000_000_000) as i64;
+                let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+                let secs_of_day = secs.rem_euclid(86400);
+
+                write!(
+                    f,
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+                    year,
+                    month,
+                    day,
+                    secs_of_day / 3600,
+                    secs_of_day / 60 % 60,
+                    secs_of_day % 60,
+                    timestamp % 1_000_000_000
+                )
+            }
+            Self::Strings(strings) => write!(f, "{:?}", strings),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{Alcov, AlcovBlock, AlcovModule, AlcovSegment};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_metadata() {
+        let mut hdr = AlcovHeader::new(None::<PathBuf>, false);
+        hdr.set_metadata(keys::TRACER, AlcovValue::String("alcov".to_string()));
+        hdr.set_metadata(
+            keys::COMMAND,
+            AlcovValue::Strings(vec!["cat".to_string(), "".to_string(), "a b".to_string()]),
+        );
+        hdr.set_metadata(
+            keys::START_TIME,
+            AlcovValue::Timestamp(1_700_000_000_123_456_789),
+        );
+        hdr.set_metadata(
+            keys::END_TIME,
+            AlcovValue::Timestamp(1_700_000_001_000_000_000),
+        );
+        hdr.set_metadata(keys::EXIT_STATUS, AlcovValue::Int(-1));
+        hdr.set_metadata("empty", AlcovValue::Strings(Vec::new()));
+        hdr.set_metadata("crashed", AlcovValue::Bool(false));
+        hdr.set_metadata("seed", AlcovValue::Bytes(vec![0xde, 0xad]));
+
+        assert_eq!(
+            hdr.get_metadata(keys::START_TIME).unwrap().to_string(),
+            "2023-11-14T22:13:20.123456789Z"
+        );
+
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap()];
+        let blocks = vec![AlcovBlock::new(0, 0, 0x10, 4, 1)];
+        let alcov = Alcov::new(hdr.clone(), modules, blocks, None);
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+
+        // a value larger than the chunk.
+        let mut metadata_buf: Vec<u8> = Vec::new();
+        metadata_buf.extend_from_slice(&1u16.to_le_bytes());
+        metadata_buf.push(0);
+        metadata_buf.extend_from_slice(&u32::MAX.to_le_bytes());
+        metadata_buf.push(b'k');
+        assert!(matches!(
+            read_metadata(&metadata_buf),
+            Err(Error::MalformedMetadata)
+        ));
+
+        let mut other = AlcovHeader::new(None::<PathBuf>, false);
+        other.set_metadata(keys::TRACER, AlcovValue::String("alcov".to_string()));
+        other.set_metadata(
+            keys::START_TIME,
+            AlcovValue::Timestamp(1_600_000_000_000_000_000),
+        );
+        other.set_metadata(
+            keys::END_TIME,
+            AlcovValue::Timestamp(1_600_000_001_000_000_000),
+        );
+        other.set_metadata(keys::EXIT_STATUS, AlcovValue::Int(0));
+
+        hdr.merge_metadata(&other);
+        hdr.merge_metadata(&other);
+        assert_eq!(
+            hdr.metadata,
+            BTreeMap::from([
+                (
+                    keys::TRACER.to_string(),
+                    AlcovValue::String("alcov".to_string())
+                ),
+                (
+                    keys::START_TIME.to_string(),
+                    AlcovValue::Timestamp(1_600_000_000_000_000_000)
+                ),
+                (
+                    keys::END_TIME.to_string(),
+                    AlcovValue::Timestamp(1_700_000_001_000_000_000)
+                ),
+                (keys::MERGED_TRACES.to_string(), AlcovValue::UInt(3)),
+            ])
+        );
+    }
+}
@@ -1,15 +1,15 @@
-use lzma_rs::lzma2_decompress;
 use std::ffi::{CStr, CString};
-use std::io;
 use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
 
 pub mod bindings;
 
 pub mod error;
 pub use error::Error;
 
+pub mod arch;
+pub use arch::{AlcovArch, AlcovEndianness, AlcovIsa, AlcovIsaMode};
+
 pub mod block;
 pub use block::{AlcovBlock, AlcovBlockMetadata};
 
@@ -19,11 +19,49 @@ pub use edge::{
     AlcovEdges,
 };
 
+pub mod checksum;
+pub use checksum::{AlcovChecksum, AlcovChecksums, AlcovHasher};
+
+pub mod code;
+
+pub mod codec;
+pub use codec::AlcovCodec;
+
+pub mod frame;
+pub use frame::{AlcovBlockFrames, AlcovFrame};
+
 pub mod header;
-pub use header::{AlcovFlags, AlcovHeader, AlcovHeaderMetadata};
+pub use header::{AlcovChunk, AlcovFlags, AlcovHeader, AlcovHeaderMetadata};
 
 pub mod module;
-pub use module::{AlcovModule, AlcovSegment};
+pub use module::{AlcovModule, AlcovPerms, AlcovSegment};
+
+pub mod elf;
+
+pub mod identity;
+pub use identity::AlcovModuleIdentity;
+
+pub mod import;
+
+pub mod input;
+pub use input::AlcovInput;
+
+pub mod maps;
+pub use maps::{MapsBacking, MapsEntry, MapsPerms, ProcMaps};
+
+pub mod merge;
+
+pub mod metadata;
+pub use metadata::AlcovValue;
+
+pub mod modoff;
+pub use modoff::{ModOffFields, ModOffLine};
+
+pub mod reader;
+pub use reader::AlcovReader;
+
+pub mod thread;
+pub use thread::AlcovThread;
 
 pub type ED = byteorder::LE;
 
@@ -33,38 +71,52 @@ pub struct Alcov {
     pub modules: Vec<AlcovModule>,
     pub blocks: Vec<AlcovBlock>,
     pub edges: Option<AlcovEdges>,
+    /// coverage of each thread, if known.
+    pub threads: Vec<AlcovThread>,
 }
 
-fn write_path(cursor: &mut Cursor<&mut Vec<u8>>, path: &Path) -> Result<i64, Error> {
-    let offset = cursor.position();
-    let path_bytes = path.as_os_str().to_str().ok_or(Error::PathEncodingError)?;
+/// Bytes of a path, as stored in the paths chunk. Paths are raw bytes on Unix, and UTF-8
+/// elsewhere.
+fn path_bytes(path: &Path) -> Result<&[u8], Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(path.as_os_str().as_bytes())
+    }
 
-    if !path_bytes.is_ascii() {
-        return Err(Error::PathEncodingError);
+    #[cfg(not(unix))]
+    {
+        path.to_str().map(str::as_bytes).ok_or(Error::PathEncodingError)
     }
+}
 
-    // we can unwrap since it's ascii
-    let path_cstr = CString::from_str(path_bytes).unwrap();
+fn write_path(cursor: &mut Cursor<&mut Vec<u8>>, path: &Path) -> Result<i64, Error> {
+    let offset = cursor.position();
+    let path_cstr = CString::new(path_bytes(path)?).map_err(|_| Error::PathEncodingError)?;
 
     cursor.write_all(path_cstr.as_bytes_with_nul())?;
 
     Ok(i64::try_from(offset)?)
 }
 
-// read size bytes from reader and put the result in a new vector.
-#[expect(clippy::uninit_vec)]
-fn read_alloc<R>(reader: &mut R, size: usize) -> Result<Vec<u8>, io::Error>
-where
-    R: Read,
-{
-    let mut buf: Vec<u8> = Vec::with_capacity(size);
-    unsafe {
-        buf.set_len(size);
-    }
+/// Read the path starting at an offset of the paths chunk.
+fn read_path(path_chunk: &[u8], offset: usize) -> Result<PathBuf, Error> {
+    let path_bytes = path_chunk
+        .get(offset..)
+        .ok_or(Error::WrongPathOffset(offset as u64))?;
+    let path_cstr = CStr::from_bytes_until_nul(path_bytes)?;
 
-    reader.read_exact(&mut buf)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(PathBuf::from(std::ffi::OsStr::from_bytes(path_cstr.to_bytes())))
+    }
 
-    Ok(buf)
+    #[cfg(not(unix))]
+    {
+        let path_str = path_cstr.to_str().map_err(|_| Error::PathEncodingError)?;
+        Ok(PathBuf::from(path_str))
+    }
 }
 
 impl Alcov {
@@ -79,6 +131,7 @@ impl Alcov {
             modules,
             blocks,
             edges,
+            threads: Vec::new(),
         }
     }
 
@@ -89,7 +142,7 @@ impl Alcov {
             flags |= AlcovFlags::InputPath;
         }
 
-        if self.hdr.compress {
+        if self.should_compress() {
             flags |= AlcovFlags::Compress;
         }
 
@@ -97,6 +150,15 @@ impl Alcov {
             flags |= AlcovFlags::Edges;
         }
 
+        let mut paths = self
+            .hdr
+            .input_path
+            .iter()
+            .chain(self.modules.iter().filter_map(|module| module.path.as_ref()));
+        if paths.any(|path| !path.as_os_str().is_ascii()) {
+            flags |= AlcovFlags::RawPaths;
+        }
+
         flags
     }
 
@@ -106,10 +168,8 @@ impl Alcov {
     {
         let flags = self.get_flags();
 
-        let modules_start: u64 = size_of::<bindings::alcov_hdr>() as u64;
-
-        let mut post_hdr_buf: Vec<u8> = Vec::new();
-        let mut post_hdr_cursor = Cursor::new(&mut post_hdr_buf);
+        let mut modules_buf: Vec<u8> = Vec::new();
+        let mut modules_cursor = Cursor::new(&mut modules_buf);
 
         let mut paths_buf: Vec<u8> = Vec::new();
         let mut paths_cursor = Cursor::new(&mut paths_buf);
@@ -126,29 +186,26 @@ impl Alcov {
                 -1
             };
 
-            module.write(&mut post_hdr_cursor, offset)?;
+            module.write(&mut modules_cursor, offset)?;
         }
 
-        let paths_start = modules_start + post_hdr_cursor.position();
+        let mut blocks_buf: Vec<u8> = Vec::new();
+        let mut blocks_cursor = Cursor::new(&mut blocks_buf);
 
-        post_hdr_cursor.write_all(&paths_buf)?;
-
-        let blocks_start = modules_start + post_hdr_cursor.position();
-
-        let edges_start: u64 = if let Some(edge) = &self.edges {
+        let edges_buf: Option<Vec<u8>> = if let Some(edge) = &self.edges {
             // edges flag should be set. otherwise, the alcov format is malformed.
             assert!(flags.intersects(AlcovFlags::Edges));
 
             let mut edges_buf: Vec<u8> = Vec::new();
             let mut edges_cursor = Cursor::new(&mut edges_buf);
 
-            let mut blocks_buf: Vec<u8> = Vec::new();
-            let mut blocks_cursor = Cursor::new(&mut blocks_buf);
+            // blocks after the last source block have no entry.
+            let no_edges = AlcovBlockEdges::default();
 
             for (i, block) in self.blocks.iter().enumerate() {
                 let offset = edges_cursor.position();
 
-                let block_edges = &edge.adj_list[i];
+                let block_edges = edge.adj_list.get(i).unwrap_or(&no_edges);
                 let block_edges_md = AlcovBlockEdgesMetadata {
                     out_edges_offset: offset,
                 };
@@ -157,46 +214,13 @@ impl Alcov {
                 block_edges.write(&mut edges_cursor)?;
             }
 
-            if flags.intersects(AlcovFlags::Compress) {
-                let mut blocks_compressed_buf: Vec<u8> = Vec::new();
-                blocks_cursor.set_position(0);
-                lzma_rs::lzma2_compress(&mut blocks_cursor, &mut blocks_compressed_buf)?;
-                post_hdr_cursor.write_all(&blocks_compressed_buf)?;
-            } else {
-                post_hdr_cursor.write_all(&blocks_buf)?;
-            }
-
-            let edge_offset = modules_start + post_hdr_cursor.position();
-
-            let mut edges_compressed_buf: Vec<u8> = Vec::new();
-            if flags.intersects(AlcovFlags::Compress) {
-                edges_cursor.set_position(0);
-                lzma_rs::lzma2_compress(&mut edges_cursor, &mut edges_compressed_buf)?;
-                post_hdr_cursor.write_all(&edges_compressed_buf)?;
-            } else {
-                post_hdr_cursor.write_all(&edges_buf)?;
-            }
-
-            post_hdr_cursor.write_all(&edges_buf)?;
-
-            edge_offset
+            Some(edges_buf)
         } else {
-            let mut blocks_buf: Vec<u8> = Vec::new();
-            let mut blocks_cursor = Cursor::new(&mut blocks_buf);
             for block in &self.blocks {
                 block.write(&mut blocks_cursor, None)?;
             }
 
-            if flags.intersects(AlcovFlags::Compress) {
-                let mut blocks_compressed_buf: Vec<u8> = Vec::new();
-                blocks_cursor.set_position(0);
-                lzma_rs::lzma2_compress(&mut blocks_cursor, &mut blocks_compressed_buf)?;
-                post_hdr_cursor.write_all(&blocks_compressed_buf)?;
-            } else {
-                post_hdr_cursor.write_all(&blocks_buf)?;
-            }
-
-            0
+            None
         };
 
         let nb_edges: u64 = if let Some(edges) = &self.edges {
@@ -205,126 +229,277 @@ impl Alcov {
             0
         };
 
-        let hdr_md = AlcovHeaderMetadata {
-            version_major: self.hdr.version_major,
-            version_minor: self.hdr.version_minor,
-            modules_start,
-            paths_start,
-            blocks_start,
-            edges_start,
+        let mut hdr_md = AlcovHeaderMetadata {
+            version_major: bindings::ALCOV_VERSION_MAJOR,
+            version_minor: bindings::ALCOV_VERSION_MINOR,
             nb_modules: u16::try_from(self.modules.len())?,
             nb_blocks: u64::try_from(self.blocks.len())?,
             nb_edges,
             flags,
+            chunks: Vec::new(),
         };
 
-        hdr_md.write(writer)?;
-        writer.write_all(&post_hdr_buf)?;
+        // chunks to write in order, with their raw size and already compressed.
+        let mut chunks: Vec<(u32, AlcovCodec, usize, Vec<u8>)> = vec![
+            (bindings::ALCOV_CHUNK_MODULES, AlcovCodec::None, modules_buf.len(), modules_buf),
+            (bindings::ALCOV_CHUNK_PATHS, AlcovCodec::None, paths_buf.len(), paths_buf),
+        ];
 
-        Ok(())
-    }
+        if !self.hdr.metadata.is_empty() {
+            let mut metadata_buf: Vec<u8> = Vec::new();
+            metadata::write_metadata(&mut metadata_buf, &self.hdr.metadata)?;
 
-    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
-    where
-        R: Read,
-    {
-        let hdr_md = AlcovHeaderMetadata::read(reader)?;
+            chunks.push((
+                bindings::ALCOV_CHUNK_METADATA,
+                AlcovCodec::None,
+                metadata_buf.len(),
+                metadata_buf,
+            ));
+        }
 
-        let modules_len = (hdr_md.paths_start - hdr_md.modules_start) as usize;
-        let modules_buf = read_alloc(reader, modules_len)?;
-        let mut modules_rdr = Cursor::new(modules_buf);
+        if let Some(input) = &self.hdr.input {
+            let mut input_buf: Vec<u8> = Vec::new();
+            input.write(&mut input_buf)?;
 
-        let paths_len = (hdr_md.blocks_start - hdr_md.paths_start) as usize;
-        let paths_buf = read_alloc(reader, paths_len)?;
+            chunks.push((
+                bindings::ALCOV_CHUNK_INPUT,
+                input.codec,
+                input_buf.len(),
+                input.codec.compress(&input_buf)?,
+            ));
+        }
 
-        let input_path: Option<PathBuf> = if hdr_md.flags.intersects(AlcovFlags::InputPath) {
-            let path_cstr = CStr::from_bytes_until_nul(&paths_buf)?;
-            let path_str = path_cstr.to_str().unwrap();
-            Some(PathBuf::from(path_str))
-        } else {
-            None
-        };
+        if self.modules.iter().any(|module| !module.identities.is_empty()) {
+            let mut identities_buf: Vec<u8> = Vec::new();
+            identity::write_identities(&mut identities_buf, &self.modules)?;
 
-        let hdr = AlcovHeader {
-            input_path,
-            version_major: hdr_md.version_major,
-            version_minor: hdr_md.version_minor,
-            compress: hdr_md.flags.intersects(AlcovFlags::Compress),
-        };
+            chunks.push((
+                bindings::ALCOV_CHUNK_MODULE_IDS,
+                AlcovCodec::None,
+                identities_buf.len(),
+                identities_buf,
+            ));
+        }
 
-        let raw_blocks_buf = if hdr_md.flags.intersects(AlcovFlags::Edges) {
-            let blocks_len = (hdr_md.edges_start - hdr_md.blocks_start) as usize;
-            read_alloc(reader, blocks_len)?
-        } else {
-            let mut blocks_buf = Vec::new();
-            reader.read_to_end(&mut blocks_buf)?;
-            blocks_buf
-        };
+        if self.modules.iter().any(AlcovModule::has_info) {
+            let mut info_buf: Vec<u8> = Vec::new();
+            for module in &self.modules {
+                module.write_info(&mut info_buf)?;
+            }
 
-        let blocks_buf = if hdr_md.flags.intersects(AlcovFlags::Compress) {
-            let mut blocks_compressed_buf_cursor = Cursor::new(&raw_blocks_buf);
+            chunks.push((
+                bindings::ALCOV_CHUNK_MODULE_INFO,
+                AlcovCodec::None,
+                info_buf.len(),
+                info_buf,
+            ));
+        }
 
-            let mut blocks_buf = Vec::new();
-            lzma2_decompress(&mut blocks_compressed_buf_cursor, &mut blocks_buf)?;
-            blocks_buf
+        let blocks_codec = self.hdr.blocks_codec;
+        if self.modules.iter().any(|module| module.code.is_some()) {
+            let mut code_buf: Vec<u8> = Vec::new();
+            code::write_code(&mut code_buf, &self.modules)?;
+
+            chunks.push((
+                bindings::ALCOV_CHUNK_MODULE_CODE,
+                blocks_codec,
+                code_buf.len(),
+                blocks_codec.compress(&code_buf)?,
+            ));
+        }
+
+        if self.blocks.iter().any(AlcovBlock::has_info) {
+            let mut info_buf: Vec<u8> = Vec::new();
+            for block in &self.blocks {
+                block.write_info(&mut info_buf)?;
+            }
+
+            chunks.push((
+                bindings::ALCOV_CHUNK_BLOCK_INFO,
+                blocks_codec,
+                info_buf.len(),
+                blocks_codec.compress(&info_buf)?,
+            ));
+        }
+
+        if !self.threads.is_empty() {
+            let mut threads_buf: Vec<u8> = Vec::new();
+            thread::write_threads(&mut threads_buf, &self.threads)?;
+
+            chunks.push((
+                bindings::ALCOV_CHUNK_THREADS,
+                blocks_codec,
+                threads_buf.len(),
+                blocks_codec.compress(&threads_buf)?,
+            ));
+        }
+
+        // compressed blocks are split in frames, to be decompressed independently.
+        if blocks_codec == AlcovCodec::None {
+            chunks.push((bindings::ALCOV_CHUNK_BLOCKS, blocks_codec, blocks_buf.len(), blocks_buf));
         } else {
-            raw_blocks_buf
-        };
-        let mut blocks_rdr = Cursor::new(blocks_buf);
+            let (frames, frames_buf) =
+                AlcovBlockFrames::compress(&blocks_buf, blocks_codec, frame::BLOCKS_PER_FRAME)?;
+
+            let mut frames_index_buf: Vec<u8> = Vec::new();
+            frames.write(&mut frames_index_buf)?;
+
+            chunks.push((
+                bindings::ALCOV_CHUNK_BLOCK_FRAMES,
+                AlcovCodec::None,
+                frames_index_buf.len(),
+                frames_index_buf,
+            ));
+            chunks.push((bindings::ALCOV_CHUNK_BLOCKS, blocks_codec, blocks_buf.len(), frames_buf));
+        }
 
-        let mut modules: Vec<AlcovModule> = Vec::new();
-        for _ in 0..hdr_md.nb_modules {
-            modules.push(AlcovModule::read(&mut modules_rdr, &paths_buf)?);
+        if let Some(edges_buf) = edges_buf {
+            let edges_codec = self.hdr.edges_codec;
+            chunks.push((
+                bindings::ALCOV_CHUNK_EDGES,
+                edges_codec,
+                edges_buf.len(),
+                edges_codec.compress(&edges_buf)?,
+            ));
         }
 
-        let mut blocks: Vec<AlcovBlock> = Vec::new();
-        if hdr_md.flags.intersects(AlcovFlags::Edges) {
-            let edges_buf: Vec<u8> = if hdr_md.flags.intersects(AlcovFlags::Compress) {
-                let mut edges_compressed_buf = Vec::new();
-                reader.read_to_end(&mut edges_compressed_buf)?;
-                let mut edges_compressed_buf_cursor = Cursor::new(edges_compressed_buf);
+        // the integrity section comes first, and is filled in once the header is known.
+        let mut checksums = self.hdr.checksum.map(|algorithm| AlcovChecksums {
+            algorithm,
+            file: 0,
+            chunks: chunks
+                .iter()
+                .map(|(chunk_type, _, _, data)| (*chunk_type as u16, algorithm.checksum(data)))
+                .collect(),
+        });
+        if let Some(checksums) = &checksums {
+            let size = AlcovChecksums::size(checksums.chunks.len());
+            chunks.insert(0, (bindings::ALCOV_CHUNK_CHECKSUMS, AlcovCodec::None, size, Vec::new()));
+        }
 
-                let mut edges_buf = Vec::new();
-                lzma2_decompress(&mut edges_compressed_buf_cursor, &mut edges_buf)?;
-                edges_buf
+        let mut offset = AlcovHeaderMetadata::size(chunks.len());
+        for (chunk_type, codec, raw_size, data) in &chunks {
+            let size = if *chunk_type == bindings::ALCOV_CHUNK_CHECKSUMS {
+                *raw_size as u64
             } else {
-                let mut edges_buf = Vec::new();
-                reader.read_to_end(&mut edges_buf)?;
-                edges_buf
+                data.len() as u64
             };
 
-            let mut edges = AlcovEdges::default();
-            for _ in 0..hdr_md.nb_blocks {
-                let (block, edge_info) = AlcovBlock::read(&mut blocks_rdr)?;
-                let out_edges = AlcovBlockEdges::read(&edges_buf, &edge_info)?;
+            hdr_md.chunks.push(AlcovChunk {
+                chunk_type: *chunk_type as u16,
+                codec: *codec,
+                offset,
+                size,
+                raw_size: *raw_size as u64,
+            });
+
+            offset += size;
+        }
+
+        let mut hdr_buf: Vec<u8> = Vec::new();
+        hdr_md.write(&mut hdr_buf)?;
 
-                blocks.push(block);
-                edges.adj_list.push(out_edges);
+        if let Some(checksums) = &mut checksums {
+            // the integrity section is still empty, so it is not part of the file checksum.
+            let mut hasher = checksums.algorithm.hasher();
+            hasher.update(&hdr_buf);
+            for (_, _, _, data) in &chunks {
+                hasher.update(data);
             }
+            checksums.file = hasher.finish();
 
-            Ok(Self {
-                hdr,
-                modules,
-                blocks,
-                edges: Some(edges),
-            })
+            checksums.write(&mut chunks[0].3)?;
+        }
+
+        writer.write_all(&hdr_buf)?;
+        for (_, _, _, data) in chunks {
+            writer.write_all(&data)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let mut alcov_rdr = AlcovReader::new(reader)?;
+        let blocks = alcov_rdr.read_blocks()?;
+
+        let edges = if alcov_rdr.has_edges() {
+            let mut edges = AlcovEdges::default();
+            edges
+                .adj_list
+                .resize(blocks.len(), AlcovBlockEdges::default());
+
+            while let Some((src, dst_edge, dst_edge_md)) = alcov_rdr.next_edge()? {
+                edges.adj_list[src as usize]
+                    .dst_modules
+                    .insert(dst_edge, dst_edge_md);
+            }
+
+            Some(edges)
         } else {
-            for _ in 0..hdr_md.nb_blocks {
-                let (block, _) = AlcovBlock::read(&mut blocks_rdr)?;
-                blocks.push(block);
+            None
+        };
+        alcov_rdr.finish()?;
+
+        Ok(Self {
+            hdr: alcov_rdr.hdr,
+            modules: alcov_rdr.modules,
+            blocks,
+            edges,
+            threads: alcov_rdr.threads,
+        })
+    }
+
+    /// Replace a module, keeping its blocks at the same absolute addresses.
+    ///
+    /// Blocks are moved to the segments of the new module. If a block does not fit in any
+    /// of them, an error is returned and nothing is modified.
+    pub fn replace_module(&mut self, module_id: u16, module: AlcovModule) -> Result<(), Error> {
+        let old_module = self
+            .modules
+            .get(module_id as usize)
+            .ok_or(Error::UnknownModule { module_id })?;
+
+        let mut rebased: Vec<(usize, u16, u64)> = Vec::new();
+        for (block_id, block) in self.blocks.iter().enumerate() {
+            if block.module_id != module_id {
+                continue;
             }
 
-            Ok(Self {
-                hdr,
-                modules,
-                blocks,
-                edges: None,
-            })
+            let old_segment = old_module
+                .segments
+                .get(block.segment_id as usize)
+                .ok_or(Error::BlockOutOfModule {
+                    block_id: block_id as u64,
+                })?;
+            let address = old_module.base_address
+                + old_segment.module_range.start
+                + block.segment_offset;
+
+            let (segment_id, segment_offset) = module
+                .locate(address.wrapping_sub(module.base_address))
+                .ok_or(Error::BlockOutOfModule {
+                    block_id: block_id as u64,
+                })?;
+
+            rebased.push((block_id, segment_id, segment_offset));
+        }
+
+        for (block_id, segment_id, segment_offset) in rebased {
+            self.blocks[block_id].segment_id = segment_id;
+            self.blocks[block_id].segment_offset = segment_offset;
         }
+
+        self.modules[module_id as usize] = module;
+
+        Ok(())
     }
 
     pub fn should_compress(&self) -> bool {
-        self.hdr.compress
+        self.hdr.compress()
     }
 
     pub fn has_edges(&self) -> bool {
@@ -339,6 +514,7 @@ impl Alcov {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     #[test]
     fn test_simple() {
@@ -394,4 +570,321 @@ mod tests {
             panic!("alcov serialization is incorrect.");
         }
     }
+
+    /// Write a file as v0.1 did, edges being written twice when compressed. If
+    /// `reverse_edges` is set, the out edges of the last block come first in the edges chunk.
+    fn write_v0_1(alcov: &Alcov, compress: bool, reverse_edges: bool) -> Vec<u8> {
+        let edges = alcov.edges.as_ref().unwrap();
+
+        let mut paths: Vec<u8> = Vec::new();
+        let mut modules: Vec<u8> = Vec::new();
+        for module in &alcov.modules {
+            let offset = match &module.path {
+                Some(path) => write_path(&mut Cursor::new(&mut paths), path).unwrap(),
+                None => -1,
+            };
+            module.write(&mut modules, offset).unwrap();
+        }
+
+        let mut out_edges: Vec<u8> = Vec::new();
+        let mut out_edges_offsets: Vec<u64> = vec![0; alcov.blocks.len()];
+        let mut block_ids: Vec<usize> = (0..alcov.blocks.len()).collect();
+        if reverse_edges {
+            block_ids.reverse();
+        }
+        for block_id in block_ids {
+            out_edges_offsets[block_id] = out_edges.len() as u64;
+            edges.adj_list[block_id].write(&mut out_edges).unwrap();
+        }
+
+        let mut blocks: Vec<u8> = Vec::new();
+        for ((block, block_edges), out_edges_offset) in alcov
+            .blocks
+            .iter()
+            .zip(&edges.adj_list)
+            .zip(out_edges_offsets)
+        {
+            let block_edges_md = AlcovBlockEdgesMetadata { out_edges_offset };
+            block.write(&mut blocks, Some((block_edges, &block_edges_md))).unwrap();
+        }
+
+        let mut chunks = vec![modules, paths];
+        if compress {
+            chunks.push(AlcovCodec::Lzma2.compress(&blocks).unwrap());
+            let mut edges_chunk = AlcovCodec::Lzma2.compress(&out_edges).unwrap();
+            edges_chunk.extend(&out_edges);
+            chunks.push(edges_chunk);
+        } else {
+            chunks.extend([blocks, out_edges]);
+        }
+
+        let mut file: Vec<u8> = Vec::new();
+        file.extend(bindings::ALCOV_MAGIC.to_le_bytes());
+        file.extend(0u64.to_le_bytes());
+        file.extend(1u64.to_le_bytes());
+        file.extend((alcov.modules.len() as u16).to_le_bytes());
+        file.extend((alcov.blocks.len() as u64).to_le_bytes());
+        file.extend(edges.nb_edges().to_le_bytes());
+
+        let mut start: u64 = 76;
+        for chunk in &chunks {
+            file.extend(start.to_le_bytes());
+            start += chunk.len() as u64;
+        }
+
+        let mut flags = AlcovFlags::Edges;
+        if compress {
+            flags |= AlcovFlags::Compress;
+        }
+        file.extend(flags.bits().to_le_bytes());
+
+        for chunk in chunks {
+            file.extend(chunk);
+        }
+
+        file
+    }
+
+    #[test]
+    fn test_read_v0_1() {
+        let modules = vec![
+            AlcovModule::new(
+                0x1000,
+                Some(PathBuf::from("/bin/a")),
+                vec![AlcovSegment::new(0..0x1000)],
+            )
+            .unwrap(),
+        ];
+        let blocks = vec![
+            AlcovBlock::new(0, 0, 0x10, 4, 2),
+            AlcovBlock::new(0, 0, 0x20, 8, 1),
+        ];
+        let mut edges = AlcovEdges::new();
+        edges.add(&blocks, 0, 1).unwrap();
+        edges.add(&blocks, 1, 0).unwrap();
+
+        for (compress, reverse_edges) in
+            [(false, false), (true, false), (false, true), (true, true)]
+        {
+            let mut alcov = Alcov::new(
+                AlcovHeader::new(None::<PathBuf>, compress),
+                modules.clone(),
+                blocks.clone(),
+                Some(edges.clone()),
+            );
+            let file = write_v0_1(&alcov, compress, reverse_edges);
+
+            let read_alcov = Alcov::read(&mut Cursor::new(file)).unwrap();
+            alcov.hdr.version_minor = 1;
+            alcov.hdr.checksum = None;
+            assert_eq!(read_alcov, alcov);
+        }
+
+        // swapped chunk offsets, at 42 + 8 * index in the header.
+        let alcov = Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, false),
+            modules,
+            blocks,
+            Some(edges),
+        );
+        for (first, chunk_type) in [
+            (1, bindings::ALCOV_CHUNK_PATHS),
+            (2, bindings::ALCOV_CHUNK_BLOCKS),
+        ] {
+            let mut file = write_v0_1(&alcov, false, false);
+            let (first_offset, second_offset) = file[42 + 8 * first..].split_at_mut(8);
+            first_offset.swap_with_slice(&mut second_offset[..8]);
+
+            assert!(matches!(
+                Alcov::read(&mut Cursor::new(file)),
+                Err(Error::MisplacedChunk(ty)) if ty == chunk_type as u16
+            ));
+        }
+    }
+
+    #[test]
+    fn test_codecs() {
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap()];
+        let blocks = vec![
+            AlcovBlock::new(0, 0, 0x10, 4, 2),
+            AlcovBlock::new(0, 0, 0x20, 8, 1),
+        ];
+        let mut edges = AlcovEdges::new();
+        edges.add(&blocks, 0, 1).unwrap();
+        edges.add(&blocks, 1, 1).unwrap();
+
+        let mut hdr = AlcovHeader::new(None::<PathBuf>, false);
+        hdr.blocks_codec = AlcovCodec::Zstd;
+        hdr.edges_codec = AlcovCodec::Lz4;
+        let alcov = Alcov::new(hdr, modules, blocks, Some(edges));
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+    }
+
+    #[test]
+    fn test_frames() {
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x100000)]).unwrap()];
+        let blocks: Vec<AlcovBlock> = (0..100000)
+            .map(|i| {
+                let mut block = AlcovBlock::new(0, 0, i * 8, 8, i % 7);
+                if i % 3 == 0 {
+                    block.mode = Some(AlcovIsaMode::Thumb);
+                    block.nb_instructions = Some(4);
+                }
+                block
+            })
+            .collect();
+        let mut edges = AlcovEdges::new();
+        for i in 0..blocks.len() as u64 {
+            edges.add(&blocks, i, (i * 31) % blocks.len() as u64).unwrap();
+        }
+
+        let mut hdr = AlcovHeader::new(None::<PathBuf>, false);
+        hdr.set_codec(AlcovCodec::Lz4);
+        let alcov = Alcov::new(hdr, modules, blocks, Some(edges));
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(&out_buf)).unwrap(), alcov);
+
+        let mut alcov_rdr = AlcovReader::new(Cursor::new(&out_buf)).unwrap();
+        let mut nb_blocks = 0;
+        while let Some(block) = alcov_rdr.next_block().unwrap() {
+            assert_eq!(block, alcov.blocks[nb_blocks]);
+            nb_blocks += 1;
+        }
+        assert_eq!(nb_blocks, alcov.blocks.len());
+
+        let mut alcov_rdr = AlcovReader::new(Cursor::new(&out_buf)).unwrap();
+        for block_id in [70000, 3, 65535, 65536, 99999] {
+            alcov_rdr.seek_block(block_id).unwrap();
+            let block = alcov_rdr.next_block().unwrap().unwrap();
+            assert_eq!(block, alcov.blocks[block_id as usize]);
+        }
+        assert!(alcov_rdr.next_block().unwrap().is_none());
+        assert!(alcov_rdr.next_edge().is_err());
+    }
+
+    #[test]
+    fn test_checksums() {
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap()];
+        let blocks = vec![
+            AlcovBlock::new(0, 0, 0x10, 4, 2),
+            AlcovBlock::new(0, 0, 0x20, 8, 1),
+        ];
+        let mut edges = AlcovEdges::new();
+        edges.add(&blocks, 0, 1).unwrap();
+        edges.add(&blocks, 1, 1).unwrap();
+
+        for (algorithm, codec) in [
+            (AlcovChecksum::Crc32c, AlcovCodec::None),
+            (AlcovChecksum::Xxh64, AlcovCodec::Zstd),
+        ] {
+            let mut hdr = AlcovHeader::new(None::<PathBuf>, false);
+            hdr.set_codec(codec);
+            hdr.checksum = Some(algorithm);
+            let alcov = Alcov::new(hdr, modules.clone(), blocks.clone(), Some(edges.clone()));
+
+            let mut out_buf: Vec<u8> = Vec::new();
+            alcov.write(&mut out_buf).unwrap();
+            assert_eq!(Alcov::read(&mut Cursor::new(&out_buf)).unwrap(), alcov);
+            assert_eq!(
+                AlcovReader::verify(Cursor::new(&out_buf)).unwrap(),
+                Some(algorithm)
+            );
+
+            let mut corrupted = out_buf.clone();
+            *corrupted.last_mut().unwrap() ^= 1;
+            assert!(Alcov::read(&mut Cursor::new(&corrupted)).is_err());
+            assert!(matches!(
+                AlcovReader::verify(Cursor::new(&corrupted)),
+                Err(Error::ChunkChecksumMismatch(_))
+            ));
+
+            let truncated = &out_buf[..out_buf.len() - 1];
+            assert!(AlcovReader::verify(Cursor::new(truncated)).is_err());
+        }
+
+        let mut hdr = AlcovHeader::new(None::<PathBuf>, false);
+        hdr.checksum = None;
+        let alcov = Alcov::new(hdr, modules, blocks, Some(edges));
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(&out_buf)).unwrap(), alcov);
+        assert_eq!(AlcovReader::verify(Cursor::new(&out_buf)).unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_raw_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let ascii_path = PathBuf::from("/bin/a");
+        let raw_path = PathBuf::from(OsStr::from_bytes(b"/home/\xe9t\xe9/\xff/b"));
+
+        let modules = vec![
+            AlcovModule::new(
+                0,
+                Some(ascii_path.clone()),
+                vec![AlcovSegment::new(0..0x1000)],
+            )
+            .unwrap(),
+        ];
+        let blocks = vec![AlcovBlock::new(0, 0, 0x10, 4, 1)];
+        let mut alcov = Alcov::new(
+            AlcovHeader::new(Some(&ascii_path), false),
+            modules,
+            blocks,
+            None,
+        );
+        assert!(!alcov.get_flags().intersects(AlcovFlags::RawPaths));
+
+        alcov.modules[0].path = Some(raw_path.clone());
+        alcov.hdr.input_path = Some(raw_path);
+        assert!(alcov.get_flags().intersects(AlcovFlags::RawPaths));
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+
+        alcov.modules[0].path = Some(PathBuf::from(OsStr::from_bytes(b"/bin/\0a")));
+        assert!(matches!(
+            alcov.write(&mut Vec::new()),
+            Err(Error::PathEncodingError)
+        ));
+
+        assert!(matches!(
+            read_path(b"/bin/a\0", 8),
+            Err(Error::WrongPathOffset(8))
+        ));
+    }
+
+    #[test]
+    fn test_replace_module() {
+        let hdr = AlcovHeader::new(None::<PathBuf>, false);
+        let modules = vec![
+            AlcovModule::new(
+                0x1000,
+                None,
+                vec![AlcovSegment::new(0..0x1000), AlcovSegment::new(0x1000..0x2000)],
+            )
+            .unwrap(),
+        ];
+        let blocks = vec![AlcovBlock::new(0, 1, 0x10, 4, 1)];
+        let mut alcov = Alcov::new(hdr, modules, blocks, None);
+
+        let module = AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x3000)]).unwrap();
+        alcov.replace_module(0, module.clone()).unwrap();
+        assert_eq!(alcov.modules[0], module);
+        assert_eq!(alcov.blocks[0].segment_id, 0);
+        assert_eq!(alcov.blocks[0].segment_offset, 0x2010);
+
+        let module = AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap();
+        assert!(alcov.replace_module(0, module).is_err());
+        assert_eq!(alcov.blocks[0].segment_offset, 0x2010);
+    }
 }
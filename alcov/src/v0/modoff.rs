@@ -0,0 +1,300 @@
+//! The "module+offset" text format.
+//!
+//! Each line describes a covered block as `<module>+<offset>[:<size>[:<count>]]`, like
+//! `libfoo.so+0x1a2b:12:3`. The module is a file name, or a full path when several
+//! modules have the same file name, and the offset is relative to the module base
+//! address. Empty lines and lines starting with `#` are ignored.
+
+use crate::v0::{Alcov, AlcovBlock, AlcovHeader, AlcovModule, AlcovSegment, Error};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// Optional fields written after the offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModOffFields {
+    pub size: bool,
+    /// Implies `size`.
+    pub count: bool,
+}
+
+/// A line of a "module+offset" file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModOffLine {
+    pub module: String,
+    pub offset: u64,
+    pub size: Option<u32>,
+    pub count: Option<u64>,
+}
+
+fn parse_number(number: &str) -> Option<u64> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
+}
+
+impl ModOffLine {
+    /// Parse a line. The offset is hexadecimal, the size and count are decimal unless
+    /// prefixed with `0x`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let (module, location) = line.trim().rsplit_once('+')?;
+        let mut fields = location.split(':');
+
+        let offset = fields.next()?;
+        let offset = u64::from_str_radix(offset.strip_prefix("0x").unwrap_or(offset), 16).ok()?;
+        let size = match fields.next() {
+            Some(size) => Some(u32::try_from(parse_number(size)?).ok()?),
+            None => None,
+        };
+        let count = match fields.next() {
+            Some(count) => Some(parse_number(count)?),
+            None => None,
+        };
+
+        if module.is_empty() || fields.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            module: module.to_string(),
+            offset,
+            size,
+            count,
+        })
+    }
+}
+
+impl Alcov {
+    /// Write the blocks in the "module+offset" format.
+    ///
+    /// Modules are named by their file name, unless another module has the same one: they
+    /// are then named by their full path. Modules without path are named `module<id>`.
+    pub fn write_modoff<W>(&self, writer: &mut W, fields: ModOffFields) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let file_names: Vec<_> = self
+            .modules
+            .iter()
+            .map(|module| module.path.as_ref().and_then(|path| path.file_name()))
+            .collect();
+
+        let names: Vec<String> = self
+            .modules
+            .iter()
+            .zip(&file_names)
+            .enumerate()
+            .map(|(module_id, (module, file_name))| {
+                let ambiguous = file_names
+                    .iter()
+                    .filter(|other| *other == file_name)
+                    .count()
+                    > 1;
+
+                match (&module.path, file_name) {
+                    (Some(path), Some(_)) if ambiguous => path.to_string_lossy().into_owned(),
+                    (_, Some(name)) => name.to_string_lossy().into_owned(),
+                    _ => format!("module{module_id}"),
+                }
+            })
+            .collect();
+
+        for (block_id, block) in self.blocks.iter().enumerate() {
+            let module =
+                self.modules
+                    .get(block.module_id as usize)
+                    .ok_or(Error::BlockOutOfModule {
+                        block_id: block_id as u64,
+                    })?;
+            let segment =
+                module
+                    .segments
+                    .get(block.segment_id as usize)
+                    .ok_or(Error::BlockOutOfModule {
+                        block_id: block_id as u64,
+                    })?;
+
+            write!(
+                writer,
+                "{}+{:#x}",
+                names[block.module_id as usize],
+                segment.module_range.start + block.segment_offset
+            )?;
+
+            if fields.size || fields.count {
+                write!(writer, ":{}", block.size)?;
+            }
+
+            if fields.count {
+                write!(writer, ":{}", block.nb_taken)?;
+            }
+
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a "module+offset" file.
+    ///
+    /// Base addresses and segments are not known: each module gets a base address of 0
+    /// and a single segment covering its blocks, and its path is the module name.
+    /// Blocks found several times are merged, adding up their counts.
+    pub fn read_modoff<R>(reader: R, hdr: AlcovHeader) -> Result<Self, Error>
+    where
+        R: BufRead,
+    {
+        let mut names: Vec<String> = Vec::new();
+        let mut ends: Vec<u64> = Vec::new();
+        let mut blocks: Vec<AlcovBlock> = Vec::new();
+        let mut block_ids: HashMap<(u16, u64), usize> = HashMap::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let modoff = ModOffLine::parse(&line).ok_or(Error::MalformedModOff { line: i + 1 })?;
+            let size = modoff.size.unwrap_or(0);
+
+            let module_id = match names.iter().position(|name| *name == modoff.module) {
+                Some(module_id) => module_id,
+                None => {
+                    names.push(modoff.module);
+                    ends.push(0);
+                    names.len() - 1
+                }
+            };
+            let end = modoff
+                .offset
+                .checked_add(size.max(1) as u64)
+                .and_then(|end| end.checked_next_multiple_of(0x1000))
+                .ok_or(Error::MalformedModOff { line: i + 1 })?;
+            ends[module_id] = ends[module_id].max(end);
+
+            let module_id = u16::try_from(module_id)?;
+            let block_id = *block_ids
+                .entry((module_id, modoff.offset))
+                .or_insert_with(|| {
+                    blocks.push(AlcovBlock::new(module_id, 0, modoff.offset, size, 0));
+                    blocks.len() - 1
+                });
+
+            let block = &mut blocks[block_id];
+            block.size = block.size.max(size);
+            block.nb_taken += modoff.count.unwrap_or(0);
+        }
+
+        let modules = names
+            .into_iter()
+            .zip(ends)
+            .map(|(name, end)| {
+                AlcovModule::new(
+                    0,
+                    Some(PathBuf::from(name)),
+                    vec![AlcovSegment::new(0..end)],
+                )
+            })
+            .collect::<Result<Vec<AlcovModule>, Error>>()?;
+
+        Ok(Self::new(hdr, modules, blocks, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const MODOFF: &str = "\
+# covered blocks
+a.out+0x1139:12:2
+libc.so.6+29d90
+
+a.out+1139:12:1
+";
+
+    #[test]
+    fn test_modoff() {
+        let alcov = Alcov::read_modoff(
+            Cursor::new(MODOFF),
+            AlcovHeader::new(None::<PathBuf>, false),
+        )
+        .unwrap();
+
+        assert_eq!(alcov.modules.len(), 2);
+        assert_eq!(alcov.modules[1].path, Some(PathBuf::from("libc.so.6")));
+        assert_eq!(
+            alcov.blocks,
+            vec![
+                AlcovBlock::new(0, 0, 0x1139, 12, 3),
+                AlcovBlock::new(1, 0, 0x29d90, 0, 0),
+            ]
+        );
+
+        let mut modoff: Vec<u8> = Vec::new();
+        alcov
+            .write_modoff(
+                &mut modoff,
+                ModOffFields {
+                    size: true,
+                    count: true,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(modoff).unwrap(),
+            "a.out+0x1139:12:3\nlibc.so.6+0x29d90:0:0\n"
+        );
+
+        assert!(matches!(
+            Alcov::read_modoff(
+                Cursor::new("a.out+0xffffffffffffffff:12\n"),
+                AlcovHeader::new(None::<PathBuf>, false),
+            ),
+            Err(Error::MalformedModOff { line: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_modoff_same_file_name() {
+        let modules = ["/usr/lib/libfoo.so", "/opt/lib/libfoo.so", "/usr/bin/a.out"]
+            .into_iter()
+            .map(|path| {
+                AlcovModule::new(
+                    0,
+                    Some(PathBuf::from(path)),
+                    vec![AlcovSegment::new(0..0x1000)],
+                )
+                .unwrap()
+            })
+            .collect();
+        let blocks = (0..3)
+            .map(|module_id| AlcovBlock::new(module_id, 0, 0x10, 4, 1))
+            .collect();
+        let alcov = Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, false),
+            modules,
+            blocks,
+            None,
+        );
+
+        let mut modoff: Vec<u8> = Vec::new();
+        alcov
+            .write_modoff(&mut modoff, ModOffFields::default())
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(modoff.clone()).unwrap(),
+            "/usr/lib/libfoo.so+0x10\n/opt/lib/libfoo.so+0x10\na.out+0x10\n"
+        );
+
+        let alcov = Alcov::read_modoff(
+            Cursor::new(modoff),
+            AlcovHeader::new(None::<PathBuf>, false),
+        )
+        .unwrap();
+        assert_eq!(alcov.modules.len(), 3);
+    }
+}
@@ -1,13 +1,26 @@
-use crate::v0::{ED, Error};
+use crate::v0::{AlcovArch, AlcovModuleIdentity, ED, Error, bindings, read_path};
+use bitflags::bitflags;
 use byteorder::{ReadBytesExt, WriteBytesExt};
-use std::ffi::CStr;
 use std::io::{Read, Write};
 use std::ops::Range;
 use std::path::PathBuf;
 
+bitflags! {
+    /// Permissions of a segment. Empty if unknown.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct AlcovPerms: u8 {
+        const Read = bindings::ALCOV_PERM_READ as u8;
+        const Write = bindings::ALCOV_PERM_WRITE as u8;
+        const Execute = bindings::ALCOV_PERM_EXECUTE as u8;
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AlcovSegment {
     pub module_range: Range<u64>,
+    /// offset of the segment in the file backing the module, if known.
+    pub file_offset: Option<u64>,
+    pub perms: AlcovPerms,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -15,11 +28,20 @@ pub struct AlcovModule {
     pub base_address: u64,
     pub path: Option<PathBuf>,
     pub segments: Vec<AlcovSegment>,
+    /// identities of the file backing the module, if known.
+    pub identities: Vec<AlcovModuleIdentity>,
+    pub arch: Option<AlcovArch>,
+    /// snapshot of the code of the module, from its base address, if captured.
+    pub code: Option<Vec<u8>>,
 }
 
 impl AlcovSegment {
     pub fn new(module_range: Range<u64>) -> Self {
-        Self { module_range }
+        Self {
+            module_range,
+            file_offset: None,
+            perms: AlcovPerms::empty(),
+        }
     }
 
     pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
@@ -39,9 +61,7 @@ impl AlcovSegment {
         let module_offset = reader.read_u64::<ED>()?;
         let size = reader.read_u64::<ED>()?;
 
-        Ok(Self {
-            module_range: module_offset..(module_offset + size),
-        })
+        Ok(Self::new(module_offset..(module_offset + size)))
     }
 }
 
@@ -59,9 +79,36 @@ impl AlcovModule {
             base_address,
             path,
             segments,
+            identities: Vec::new(),
+            arch: None,
+            code: None,
         })
     }
 
+    /// Find the segment containing an offset from the module base address.
+    ///
+    /// Returns the segment ID and the offset in that segment.
+    pub fn locate(&self, module_offset: u64) -> Option<(u16, u64)> {
+        let segment_id = self
+            .segments
+            .iter()
+            .position(|segment| segment.module_range.contains(&module_offset))?;
+
+        Some((
+            u16::try_from(segment_id).ok()?,
+            module_offset - self.segments[segment_id].module_range.start,
+        ))
+    }
+
+    /// Offset from the module base address of a location in a segment.
+    ///
+    /// The inverse of [`AlcovModule::locate`].
+    pub fn module_offset(&self, segment_id: u16, segment_offset: u64) -> Option<u64> {
+        let segment = self.segments.get(segment_id as usize)?;
+
+        Some(segment.module_range.start + segment_offset)
+    }
+
     pub fn write<W>(&self, writer: &mut W, path_offset: i64) -> Result<(), Error>
     where
         W: Write,
@@ -95,10 +142,7 @@ impl AlcovModule {
         }
 
         let path = if path_offset >= 0 {
-            let path_cstr =
-                CStr::from_bytes_until_nul(&path_chunk[(path_offset as usize)..path_chunk.len()])?;
-            let path_str = path_cstr.to_str().unwrap();
-            Some(PathBuf::from(path_str))
+            Some(read_path(path_chunk, path_offset as usize)?)
         } else {
             None
         };
@@ -107,6 +151,55 @@ impl AlcovModule {
             base_address,
             path,
             segments,
+            identities: Vec::new(),
+            arch: None,
+            code: None,
         })
     }
 }
+
+impl AlcovModule {
+    /// Whether the module has anything to write in the module info chunk.
+    pub fn has_info(&self) -> bool {
+        self.arch.is_some()
+            || self
+                .segments
+                .iter()
+                .any(|segment| segment.file_offset.is_some() || !segment.perms.is_empty())
+    }
+
+    /// Write the entry of the module in the module info chunk.
+    pub fn write_info<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        AlcovArch::write(self.arch.as_ref(), writer)?;
+
+        for segment in &self.segments {
+            let file_offset = segment
+                .file_offset
+                .unwrap_or(bindings::ALCOV_NO_FILE_OFFSET);
+            writer.write_u64::<ED>(file_offset)?;
+            writer.write_u8(segment.perms.bits())?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the entry of the module in the module info chunk.
+    pub fn read_info<R>(&mut self, reader: &mut R) -> Result<(), Error>
+    where
+        R: Read,
+    {
+        self.arch = AlcovArch::read(reader)?;
+
+        for segment in &mut self.segments {
+            let file_offset = reader.read_u64::<ED>()?;
+            segment.file_offset =
+                (file_offset != bindings::ALCOV_NO_FILE_OFFSET).then_some(file_offset);
+            segment.perms = AlcovPerms::from_bits_truncate(reader.read_u8()?);
+        }
+
+        Ok(())
+    }
+}
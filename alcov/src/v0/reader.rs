@@ -0,0 +1,673 @@
+use crate::v0::{
+    AlcovBlock, AlcovBlockFrames, AlcovBlockMetadata, AlcovChecksum, AlcovChecksums, AlcovChunk,
+    AlcovCodec, AlcovDstBlockEdge, AlcovDstBlockEdgeMetadata, AlcovFlags, AlcovHasher, AlcovHeader,
+    AlcovHeaderMetadata, AlcovInput, AlcovModule, AlcovThread, Error, bindings, code, identity,
+    metadata, read_path, thread,
+};
+use std::collections::BTreeMap;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+/// Reader keeping track of its offset in the file.
+struct ChunkReader<R> {
+    reader: R,
+    position: u64,
+    /// checksums being computed over the bytes read, if the file has an integrity section.
+    checks: Option<Checks>,
+}
+
+/// Checksums of the chunks and of the whole file, computed while reading.
+struct Checks {
+    checksums: AlcovChecksums,
+    file_hasher: AlcovHasher,
+    /// chunks with a checksum, and their checksum so far.
+    chunks: Vec<(AlcovChunk, AlcovHasher)>,
+    /// end of the last chunk.
+    end: u64,
+    /// first chunk found with a wrong checksum.
+    mismatch: Option<u16>,
+}
+
+impl Checks {
+    fn new(checksums: AlcovChecksums, hdr_md: &AlcovHeaderMetadata, hdr_buf: &[u8]) -> Self {
+        let mut file_hasher = checksums.algorithm.hasher();
+        file_hasher.update(hdr_buf);
+
+        let chunks = hdr_md
+            .chunks
+            .iter()
+            .filter(|chunk| checksums.chunk(chunk.chunk_type).is_some())
+            .map(|chunk| (chunk.clone(), checksums.algorithm.hasher()))
+            .collect();
+        let end = hdr_md
+            .chunks
+            .iter()
+            .map(|chunk| chunk.offset + chunk.size)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            checksums,
+            file_hasher,
+            chunks,
+            end,
+            mismatch: None,
+        }
+    }
+
+    /// Add bytes read at a position of the file.
+    fn update(&mut self, position: u64, data: &[u8]) {
+        let data_end = position + data.len() as u64;
+
+        if position < self.end {
+            let len = (data_end.min(self.end) - position) as usize;
+            self.file_hasher.update(&data[..len]);
+        }
+
+        for (chunk, hasher) in &mut self.chunks {
+            let chunk_end = chunk.offset + chunk.size;
+            let start = chunk.offset.max(position);
+            let end = chunk_end.min(data_end);
+            if start >= end {
+                continue;
+            }
+
+            hasher.update(&data[(start - position) as usize..(end - position) as usize]);
+            if end == chunk_end
+                && self.checksums.chunk(chunk.chunk_type) != Some(hasher.finish())
+                && self.mismatch.is_none()
+            {
+                self.mismatch = Some(chunk.chunk_type);
+            }
+        }
+    }
+}
+
+impl<R> Read for ChunkReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read(buf)?;
+        if let Some(checks) = &mut self.checks {
+            checks.update(self.position, &buf[..len]);
+        }
+        self.position += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<R> ChunkReader<R>
+where
+    R: Read,
+{
+    /// Read the header, and the integrity section if any, so that every byte read
+    /// afterwards is checked.
+    fn open(reader: R) -> Result<(Self, AlcovHeaderMetadata), Error> {
+        let mut reader = Self {
+            reader,
+            position: 0,
+            checks: None,
+        };
+        let hdr_md = AlcovHeaderMetadata::read(&mut reader)?;
+
+        if let Some(chunk) = hdr_md.chunk(bindings::ALCOV_CHUNK_CHECKSUMS as u16) {
+            if chunk.offset != reader.position {
+                return Err(Error::MisplacedChunk(chunk.chunk_type));
+            }
+
+            let checksums = AlcovChecksums::read(&mut Cursor::new(reader.read_chunk(chunk)?))?;
+
+            let mut hdr_buf: Vec<u8> = Vec::new();
+            hdr_md.write(&mut hdr_buf)?;
+            reader.checks = Some(Checks::new(checksums, &hdr_md, &hdr_buf));
+        }
+
+        Ok((reader, hdr_md))
+    }
+
+    fn algorithm(&self) -> Option<AlcovChecksum> {
+        self.checks
+            .as_ref()
+            .map(|checks| checks.checksums.algorithm)
+    }
+
+    /// Fail if a chunk read so far has a wrong checksum.
+    fn check(&self) -> Result<(), Error> {
+        match self.checks.as_ref().and_then(|checks| checks.mismatch) {
+            Some(chunk_type) => Err(Error::ChunkChecksumMismatch(chunk_type)),
+            None => Ok(()),
+        }
+    }
+
+    /// Read until the end of the last chunk, and check every checksum.
+    fn finish(&mut self) -> Result<(), Error> {
+        let Some(end) = self.checks.as_ref().map(|checks| checks.end) else {
+            return Ok(());
+        };
+
+        let skip = end.saturating_sub(self.position);
+        if io::copy(&mut self.by_ref().take(skip), &mut io::sink())? != skip {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        self.check()?;
+
+        let checks = self.checks.as_ref().unwrap();
+        if checks.file_hasher.finish() != checks.checksums.file {
+            return Err(Error::FileChecksumMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Skip bytes until the start of a chunk.
+    fn seek_chunk(&mut self, chunk: &AlcovChunk) -> Result<(), Error> {
+        let skip = chunk
+            .offset
+            .checked_sub(self.position)
+            .ok_or(Error::MisplacedChunk(chunk.chunk_type))?;
+        io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+
+        Ok(())
+    }
+
+    /// Read a frame of the blocks chunk, still compressed.
+    fn read_frame(&mut self, frames: &BlockFrames, frame_id: usize) -> Result<Vec<u8>, Error> {
+        let frame = frames
+            .index
+            .frames
+            .get(frame_id)
+            .ok_or(Error::MalformedFrames)?;
+
+        let skip = (frames.chunk_offset + frame.offset)
+            .checked_sub(self.position)
+            .ok_or(Error::MalformedFrames)?;
+        io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+
+        let mut data = Vec::new();
+        self.by_ref().take(frame.size).read_to_end(&mut data)?;
+        if data.len() as u64 != frame.size {
+            return Err(Error::MalformedFrames);
+        }
+        self.check()?;
+
+        Ok(data)
+    }
+
+    /// Read a whole chunk in memory, and decompress it.
+    fn read_chunk(&mut self, chunk: &AlcovChunk) -> Result<Vec<u8>, Error> {
+        self.seek_chunk(chunk)?;
+
+        let mut data = Vec::new();
+        self.by_ref().take(chunk.size).read_to_end(&mut data)?;
+        self.check()?;
+
+        chunk.codec.decompress(data, chunk.raw_size)
+    }
+}
+
+/// Where the blocks or edges are read from.
+enum ChunkSource {
+    /// Directly from the underlying reader.
+    Stream,
+    /// From a decompressed chunk.
+    Buffer(Cursor<Vec<u8>>),
+}
+
+/// Frames of the blocks chunk, decompressed one at a time.
+struct BlockFrames {
+    index: AlcovBlockFrames,
+    codec: AlcovCodec,
+    chunk_offset: u64,
+    next_frame: usize,
+}
+
+/// Read an alcov file block by block, and edge by edge, without keeping them in memory.
+///
+/// The header and the modules are read when the reader is created. Blocks are then
+/// returned by [`AlcovReader::next_block`], and edges by [`AlcovReader::next_edge`] once
+/// every block has been read. Edges are streamed when they are in the order of their
+/// source block, as written by [`crate::v0::Alcov::write`], and read from the edges chunk
+/// loaded in memory otherwise.
+///
+/// Compressed chunks are decompressed in memory as a whole, except for the blocks chunk
+/// when it is split in frames: frames are then decompressed when needed, and the reader
+/// can move to any block with [`AlcovReader::seek_block`] if the underlying reader is
+/// seekable.
+pub struct AlcovReader<R> {
+    reader: ChunkReader<R>,
+    pub hdr: AlcovHeader,
+    pub modules: Vec<AlcovModule>,
+    /// coverage of each thread, if known.
+    pub threads: Vec<AlcovThread>,
+    hdr_md: AlcovHeaderMetadata,
+    blocks: ChunkSource,
+    /// frames of the blocks chunk. the current frame is in the blocks buffer.
+    frames: Option<BlockFrames>,
+    /// block info chunk, if any.
+    block_info: Option<Cursor<Vec<u8>>>,
+    nb_read_blocks: u64,
+    /// whether blocks have been skipped or read again by seeking.
+    seeked: bool,
+    /// number of out edges of each block read so far, and their offset in the edges chunk.
+    out_edges: Vec<(u64, u64)>,
+    edges: Option<ChunkSource>,
+    /// source block of the next edge, and its remaining out edges.
+    edge_src: (usize, u64),
+}
+
+impl<R> AlcovReader<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Result<Self, Error> {
+        let (mut reader, hdr_md) = ChunkReader::open(reader)?;
+
+        let chunk = |chunk_type: u32| -> Result<AlcovChunk, Error> {
+            hdr_md
+                .chunk(chunk_type as u16)
+                .cloned()
+                .ok_or(Error::MissingChunk(chunk_type as u16))
+        };
+
+        let modules_buf = reader.read_chunk(&chunk(bindings::ALCOV_CHUNK_MODULES)?)?;
+        let mut modules_rdr = Cursor::new(modules_buf);
+
+        let paths_buf = reader.read_chunk(&chunk(bindings::ALCOV_CHUNK_PATHS)?)?;
+
+        let input_path: Option<PathBuf> = if hdr_md.flags.intersects(AlcovFlags::InputPath) {
+            Some(read_path(&paths_buf, 0)?)
+        } else {
+            None
+        };
+
+        let metadata = match hdr_md.chunk(bindings::ALCOV_CHUNK_METADATA as u16) {
+            Some(metadata_chunk) => metadata::read_metadata(&reader.read_chunk(metadata_chunk)?)?,
+            None => BTreeMap::new(),
+        };
+
+        let input = match hdr_md.chunk(bindings::ALCOV_CHUNK_INPUT as u16) {
+            Some(input_chunk) => Some(AlcovInput::read(
+                reader.read_chunk(input_chunk)?,
+                input_chunk.codec,
+            )?),
+            None => None,
+        };
+
+        let blocks_chunk = chunk(bindings::ALCOV_CHUNK_BLOCKS)?;
+        let edges_codec = if hdr_md.flags.intersects(AlcovFlags::Edges) {
+            chunk(bindings::ALCOV_CHUNK_EDGES)?.codec
+        } else {
+            AlcovCodec::None
+        };
+
+        let hdr = AlcovHeader {
+            input_path,
+            version_major: hdr_md.version_major,
+            version_minor: hdr_md.version_minor,
+            blocks_codec: blocks_chunk.codec,
+            edges_codec,
+            checksum: reader.algorithm(),
+            input,
+            metadata,
+        };
+
+        let mut modules: Vec<AlcovModule> = Vec::new();
+        for _ in 0..hdr_md.nb_modules {
+            modules.push(AlcovModule::read(&mut modules_rdr, &paths_buf)?);
+        }
+
+        if let Some(identities_chunk) = hdr_md.chunk(bindings::ALCOV_CHUNK_MODULE_IDS as u16) {
+            identity::read_identities(&reader.read_chunk(identities_chunk)?, &mut modules)?;
+        }
+
+        if let Some(info_chunk) = hdr_md.chunk(bindings::ALCOV_CHUNK_MODULE_INFO as u16) {
+            let mut info_rdr = Cursor::new(reader.read_chunk(info_chunk)?);
+            for module in &mut modules {
+                module.read_info(&mut info_rdr)?;
+            }
+        }
+
+        if let Some(code_chunk) = hdr_md.chunk(bindings::ALCOV_CHUNK_MODULE_CODE as u16) {
+            code::read_code(&reader.read_chunk(code_chunk)?, &mut modules)?;
+        }
+
+        let block_info = match hdr_md.chunk(bindings::ALCOV_CHUNK_BLOCK_INFO as u16) {
+            Some(info_chunk) => Some(Cursor::new(reader.read_chunk(info_chunk)?)),
+            None => None,
+        };
+
+        let threads = match hdr_md.chunk(bindings::ALCOV_CHUNK_THREADS as u16) {
+            Some(threads_chunk) => thread::read_threads(&reader.read_chunk(threads_chunk)?)?,
+            None => Vec::new(),
+        };
+
+        let frames_chunk = hdr_md
+            .chunk(bindings::ALCOV_CHUNK_BLOCK_FRAMES as u16)
+            .cloned();
+
+        let (blocks, frames) = if blocks_chunk.codec == AlcovCodec::None {
+            reader.seek_chunk(&blocks_chunk)?;
+            (ChunkSource::Stream, None)
+        } else if let Some(frames_chunk) = frames_chunk {
+            let index = AlcovBlockFrames::read(
+                &mut Cursor::new(reader.read_chunk(&frames_chunk)?),
+                hdr_md.nb_blocks,
+            )?;
+
+            let frames = BlockFrames {
+                index,
+                codec: blocks_chunk.codec,
+                chunk_offset: blocks_chunk.offset,
+                next_frame: 0,
+            };
+            (ChunkSource::Buffer(Cursor::new(Vec::new())), Some(frames))
+        } else {
+            (
+                ChunkSource::Buffer(Cursor::new(reader.read_chunk(&blocks_chunk)?)),
+                None,
+            )
+        };
+
+        Ok(Self {
+            reader,
+            hdr,
+            modules,
+            threads,
+            hdr_md,
+            blocks,
+            frames,
+            block_info,
+            nb_read_blocks: 0,
+            seeked: false,
+            out_edges: Vec::new(),
+            edges: None,
+            edge_src: (0, 0),
+        })
+    }
+
+    pub fn nb_blocks(&self) -> u64 {
+        self.hdr_md.nb_blocks
+    }
+
+    pub fn nb_edges(&self) -> u64 {
+        self.hdr_md.nb_edges
+    }
+
+    pub fn has_edges(&self) -> bool {
+        self.hdr_md.flags.intersects(AlcovFlags::Edges)
+    }
+
+    fn block_read(
+        &mut self,
+        block: &mut AlcovBlock,
+        block_md: &AlcovBlockMetadata,
+    ) -> Result<(), Error> {
+        if let Some(info_rdr) = &mut self.block_info {
+            info_rdr
+                .set_position(self.nb_read_blocks * size_of::<bindings::alcov_block_info>() as u64);
+            block.read_info(info_rdr)?;
+        }
+
+        self.nb_read_blocks += 1;
+        if self.has_edges() && !self.seeked {
+            self.out_edges
+                .push((block_md.nb_out_edges, block_md.out_edges_offset));
+        }
+
+        Ok(())
+    }
+
+    /// Read the next block, or `None` once every block has been read.
+    pub fn next_block(&mut self) -> Result<Option<AlcovBlock>, Error> {
+        if self.nb_read_blocks == self.hdr_md.nb_blocks {
+            return Ok(None);
+        }
+
+        let (mut block, block_md) = match &mut self.blocks {
+            ChunkSource::Stream => {
+                let block = AlcovBlock::read(&mut self.reader)?;
+                self.reader.check()?;
+                block
+            }
+            ChunkSource::Buffer(blocks_rdr) => {
+                if let Some(frames) = &mut self.frames
+                    && blocks_rdr.position() == blocks_rdr.get_ref().len() as u64
+                {
+                    let frame_id = frames.next_frame;
+                    let data = self.reader.read_frame(frames, frame_id)?;
+                    let raw = frames.index.decompress(
+                        &[(frame_id, &data)],
+                        frames.codec,
+                        self.hdr_md.nb_blocks,
+                    )?;
+
+                    *blocks_rdr = Cursor::new(raw.into_iter().next().unwrap());
+                    frames.next_frame += 1;
+                }
+
+                AlcovBlock::read(blocks_rdr)?
+            }
+        };
+
+        self.block_read(&mut block, &block_md)?;
+
+        Ok(Some(block))
+    }
+
+    /// Read every remaining block. Frames of the blocks chunk are decompressed in parallel.
+    pub fn read_blocks(&mut self) -> Result<Vec<AlcovBlock>, Error> {
+        let mut blocks: Vec<AlcovBlock> = Vec::new();
+
+        if let (ChunkSource::Buffer(blocks_rdr), Some(frames)) =
+            (&mut self.blocks, &mut self.frames)
+        {
+            // end of the current frame.
+            let mut raws: Vec<Vec<u8>> =
+                vec![blocks_rdr.get_ref()[blocks_rdr.position() as usize..].to_vec()];
+
+            let mut frames_data: Vec<(usize, Vec<u8>)> = Vec::new();
+            for frame_id in frames.next_frame..frames.index.frames.len() {
+                frames_data.push((frame_id, self.reader.read_frame(frames, frame_id)?));
+            }
+            let frames_data: Vec<(usize, &[u8])> = frames_data
+                .iter()
+                .map(|(frame_id, data)| (*frame_id, data.as_slice()))
+                .collect();
+
+            raws.extend(frames.index.decompress(
+                &frames_data,
+                frames.codec,
+                self.hdr_md.nb_blocks,
+            )?);
+            frames.next_frame = frames.index.frames.len();
+            *blocks_rdr = Cursor::new(Vec::new());
+
+            for raw in raws {
+                let mut raw_rdr = Cursor::new(raw);
+                while raw_rdr.position() < raw_rdr.get_ref().len() as u64
+                    && self.nb_read_blocks < self.hdr_md.nb_blocks
+                {
+                    let (mut block, block_md) = AlcovBlock::read(&mut raw_rdr)?;
+                    self.block_read(&mut block, &block_md)?;
+                    blocks.push(block);
+                }
+            }
+        }
+
+        while let Some(block) = self.next_block()? {
+            blocks.push(block);
+        }
+
+        Ok(blocks)
+    }
+
+    /// Read the next edge as its source block ID, its destination and its metadata, or
+    /// `None` once every edge has been read. The remaining blocks are skipped.
+    pub fn next_edge(
+        &mut self,
+    ) -> Result<Option<(u64, AlcovDstBlockEdge, AlcovDstBlockEdgeMetadata)>, Error> {
+        if !self.has_edges() {
+            return Ok(None);
+        }
+
+        if self.seeked {
+            return Err(Error::EdgesAfterSeek);
+        }
+
+        while self.next_block()?.is_some() {}
+
+        let edges = match &mut self.edges {
+            Some(edges) => edges,
+            None => {
+                let edges_chunk = self
+                    .hdr_md
+                    .chunk(bindings::ALCOV_CHUNK_EDGES as u16)
+                    .cloned()
+                    .ok_or(Error::MissingChunk(bindings::ALCOV_CHUNK_EDGES as u16))?;
+
+                // edges can only be streamed if each block's edges follow the previous ones.
+                let mut position: u64 = 0;
+                let in_order = self.out_edges.iter().all(|&(nb_out_edges, offset)| {
+                    let in_order = nb_out_edges == 0 || offset == position;
+                    position = position.saturating_add(nb_out_edges.saturating_mul(size_of::<
+                        bindings::alcov_out_edge,
+                    >(
+                    )
+                        as u64));
+                    in_order
+                });
+
+                let mut edges = if edges_chunk.codec == AlcovCodec::None && in_order {
+                    self.reader.seek_chunk(&edges_chunk)?;
+                    ChunkSource::Stream
+                } else {
+                    ChunkSource::Buffer(Cursor::new(self.reader.read_chunk(&edges_chunk)?))
+                };
+
+                let (nb_out_edges, offset) = self.out_edges.first().copied().unwrap_or_default();
+                if let ChunkSource::Buffer(edges_rdr) = &mut edges {
+                    edges_rdr.set_position(offset);
+                }
+
+                self.edge_src = (0, nb_out_edges);
+                self.edges.insert(edges)
+            }
+        };
+
+        // skip blocks without any more out edges.
+        while self.edge_src.1 == 0 {
+            if self.edge_src.0 + 1 >= self.out_edges.len() {
+                return Ok(None);
+            }
+
+            self.edge_src.0 += 1;
+            let (nb_out_edges, offset) = self.out_edges[self.edge_src.0];
+            self.edge_src.1 = nb_out_edges;
+            if let ChunkSource::Buffer(edges_rdr) = edges {
+                edges_rdr.set_position(offset);
+            }
+        }
+
+        let (dst_edge, dst_edge_md) = match edges {
+            ChunkSource::Stream => {
+                let edge = (
+                    AlcovDstBlockEdge::read(&mut self.reader)?,
+                    AlcovDstBlockEdgeMetadata::read(&mut self.reader)?,
+                );
+                self.reader.check()?;
+                edge
+            }
+            ChunkSource::Buffer(edges_rdr) => (
+                AlcovDstBlockEdge::read(edges_rdr)?,
+                AlcovDstBlockEdgeMetadata::read(edges_rdr)?,
+            ),
+        };
+        self.edge_src.1 -= 1;
+
+        Ok(Some((self.edge_src.0 as u64, dst_edge, dst_edge_md)))
+    }
+
+    /// Read the rest of the file, and check the checksums of every chunk and of the
+    /// whole file if it has an integrity section.
+    ///
+    /// Checksums are otherwise checked as chunks are read, but a streamed chunk is only
+    /// checked once read until its end.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        self.reader.finish()
+    }
+
+    /// Check the checksums of a file without decoding it, returning the algorithm of
+    /// its integrity section, or `None` if it has none.
+    pub fn verify(reader: R) -> Result<Option<AlcovChecksum>, Error> {
+        let (mut reader, _) = ChunkReader::open(reader)?;
+        reader.finish()?;
+
+        Ok(reader.algorithm())
+    }
+}
+
+impl<R> AlcovReader<R>
+where
+    R: Read + Seek,
+{
+    /// Move to a block, so that it is returned by the next call to
+    /// [`AlcovReader::next_block`]. Only the frame holding the block is decompressed.
+    ///
+    /// Edges can no longer be read afterwards, and checksums are no longer checked.
+    pub fn seek_block(&mut self, block_id: u64) -> Result<(), Error> {
+        let block_id = block_id.min(self.hdr_md.nb_blocks);
+        let block_size = size_of::<bindings::alcov_block>() as u64;
+        self.reader.checks = None;
+
+        match (&mut self.blocks, &mut self.frames) {
+            (ChunkSource::Stream, _) => {
+                let blocks_chunk = self
+                    .hdr_md
+                    .chunk(bindings::ALCOV_CHUNK_BLOCKS as u16)
+                    .ok_or(Error::MissingChunk(bindings::ALCOV_CHUNK_BLOCKS as u16))?;
+                let offset = blocks_chunk.offset + block_id * block_size;
+
+                self.reader.reader.seek(SeekFrom::Start(offset))?;
+                self.reader.position = offset;
+            }
+            (ChunkSource::Buffer(blocks_rdr), Some(frames)) => {
+                let frame_id = frames.index.frame_of(block_id);
+
+                if block_id < self.hdr_md.nb_blocks {
+                    let frame = frames
+                        .index
+                        .frames
+                        .get(frame_id)
+                        .ok_or(Error::MalformedFrames)?;
+                    let offset = frames.chunk_offset + frame.offset;
+                    self.reader.reader.seek(SeekFrom::Start(offset))?;
+                    self.reader.position = offset;
+
+                    let data = self.reader.read_frame(frames, frame_id)?;
+                    let raw = frames.index.decompress(
+                        &[(frame_id, &data)],
+                        frames.codec,
+                        self.hdr_md.nb_blocks,
+                    )?;
+                    *blocks_rdr = Cursor::new(raw.into_iter().next().unwrap());
+                } else {
+                    *blocks_rdr = Cursor::new(Vec::new());
+                }
+
+                let first_block = frame_id as u64 * frames.index.nb_blocks_per_frame;
+                blocks_rdr.set_position((block_id - first_block) * block_size);
+                frames.next_frame = frame_id + 1;
+            }
+            (ChunkSource::Buffer(blocks_rdr), None) => {
+                blocks_rdr.set_position(block_id * block_size);
+            }
+        }
+
+        self.nb_read_blocks = block_id;
+        self.seeked = true;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use crate::v0::{Alcov, AlcovBlockEdges, AlcovDstBlockEdgeMetadata, AlcovEdges, ED, Error};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+
+/// Coverage of a single thread of the traced program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlcovThread {
+    /// thread id, as given by the operating system.
+    pub tid: u64,
+    pub name: Option<String>,
+    /// hit count of the blocks executed by the thread, by block id.
+    pub blocks: BTreeMap<u64, u64>,
+    /// hit count of the edges taken by the thread, by source and destination block ids.
+    pub edges: BTreeMap<(u64, u64), u64>,
+}
+
+impl AlcovThread {
+    pub fn new(tid: u64, name: Option<String>) -> Self {
+        Self {
+            tid,
+            name,
+            blocks: BTreeMap::new(),
+            edges: BTreeMap::new(),
+        }
+    }
+
+    /// Count hits of a block by the thread, saturating at `u64::MAX`.
+    pub fn add_block(&mut self, block_id: u64, nb_taken: u64) {
+        let count = self.blocks.entry(block_id).or_default();
+        *count = count.saturating_add(nb_taken);
+    }
+
+    /// Count hits of an edge by the thread, saturating at `u64::MAX`.
+    pub fn add_edge(&mut self, src_block: u64, dst_block: u64, nb_taken: u64) {
+        let count = self.edges.entry((src_block, dst_block)).or_default();
+        *count = count.saturating_add(nb_taken);
+    }
+
+    /// total number of block hits of the thread.
+    pub fn nb_taken(&self) -> u64 {
+        self.blocks
+            .values()
+            .fold(0, |total, nb_taken| total.saturating_add(*nb_taken))
+    }
+
+    /// Check that the blocks of the thread are blocks of the trace.
+    fn check(&self, nb_blocks: usize) -> Result<(), Error> {
+        let edge_blocks = self.edges.keys().flat_map(|&(src, dst)| [src, dst]);
+
+        match self
+            .blocks
+            .keys()
+            .copied()
+            .chain(edge_blocks)
+            .find(|&block_id| block_id >= nb_blocks as u64)
+        {
+            Some(block_id) => Err(Error::ThreadWithoutBlock {
+                tid: self.tid,
+                block_id,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write<W>(&self, writer: &mut W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let name = self.name.as_deref().unwrap_or_default();
+
+        writer.write_u64::<ED>(self.tid)?;
+        writer.write_u64::<ED>(self.blocks.len() as u64)?;
+        writer.write_u64::<ED>(self.edges.len() as u64)?;
+        writer.write_u16::<ED>(u16::try_from(name.len())?)?;
+        writer.write_all(name.as_bytes())?;
+
+        for (block_id, nb_taken) in &self.blocks {
+            writer.write_u64::<ED>(*block_id)?;
+            writer.write_u64::<ED>(*nb_taken)?;
+        }
+
+        for ((src_block, dst_block), nb_taken) in &self.edges {
+            writer.write_u64::<ED>(*src_block)?;
+            writer.write_u64::<ED>(*dst_block)?;
+            writer.write_u64::<ED>(*nb_taken)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R>(reader: &mut R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        let tid = reader.read_u64::<ED>()?;
+        let nb_blocks = reader.read_u64::<ED>()?;
+        let nb_edges = reader.read_u64::<ED>()?;
+        let name_size = reader.read_u16::<ED>()?;
+
+        let mut name = vec![0u8; name_size as usize];
+        reader.read_exact(&mut name)?;
+        let name = match name_size {
+            0 => None,
+            _ => Some(String::from_utf8(name).map_err(|_| Error::MalformedThreads)?),
+        };
+
+        let mut thread = Self::new(tid, name);
+        for _ in 0..nb_blocks {
+            let block_id = reader.read_u64::<ED>()?;
+            thread.add_block(block_id, reader.read_u64::<ED>()?);
+        }
+
+        for _ in 0..nb_edges {
+            let src_block = reader.read_u64::<ED>()?;
+            let dst_block = reader.read_u64::<ED>()?;
+            thread.add_edge(src_block, dst_block, reader.read_u64::<ED>()?);
+        }
+
+        Ok(thread)
+    }
+}
+
+impl Alcov {
+    pub fn thread(&self, tid: u64) -> Option<&AlcovThread> {
+        self.threads.iter().find(|thread| thread.tid == tid)
+    }
+
+    /// Coverage of a single thread.
+    ///
+    /// Blocks keep their ids, with the hit counts of the thread: blocks it did not execute
+    /// are never taken. If the trace has edges, only the edges taken by the thread are kept.
+    pub fn project_thread(&self, tid: u64) -> Result<Alcov, Error> {
+        let thread = self.thread(tid).ok_or(Error::UnknownThread(tid))?;
+        thread.check(self.blocks.len())?;
+
+        let mut alcov = Alcov::new(
+            self.hdr.clone(),
+            self.modules.clone(),
+            self.blocks.clone(),
+            self.edges
+                .as_ref()
+                .map(|_| thread_edges(thread, self.blocks.len())),
+        );
+        for (block_id, block) in alcov.blocks.iter_mut().enumerate() {
+            block.nb_taken = thread.blocks.get(&(block_id as u64)).copied().unwrap_or(0);
+        }
+        alcov.threads = vec![thread.clone()];
+
+        Ok(alcov)
+    }
+
+    /// Set the hit counts of blocks, and of edges if the trace has edges, to the sum of the
+    /// hit counts of all threads.
+    ///
+    /// Nothing is done if the trace has no threads.
+    pub fn aggregate_threads(&mut self) -> Result<(), Error> {
+        if self.threads.is_empty() {
+            return Ok(());
+        }
+
+        let mut all = AlcovThread::new(0, None);
+        for thread in &self.threads {
+            thread.check(self.blocks.len())?;
+
+            for (&block_id, &nb_taken) in &thread.blocks {
+                all.add_block(block_id, nb_taken);
+            }
+            for (&(src_block, dst_block), &nb_taken) in &thread.edges {
+                all.add_edge(src_block, dst_block, nb_taken);
+            }
+        }
+
+        for (block_id, block) in self.blocks.iter_mut().enumerate() {
+            block.nb_taken = all.blocks.get(&(block_id as u64)).copied().unwrap_or(0);
+        }
+        if self.edges.is_some() {
+            self.edges = Some(thread_edges(&all, self.blocks.len()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Edges taken by a thread.
+fn thread_edges(thread: &AlcovThread, nb_blocks: usize) -> AlcovEdges {
+    let mut edges = AlcovEdges::new();
+    edges.adj_list.resize(nb_blocks, AlcovBlockEdges::default());
+
+    for (&(src_block, dst_block), &nb_taken) in &thread.edges {
+        edges.adj_list[src_block as usize]
+            .dst_modules
+            .insert(dst_block.into(), AlcovDstBlockEdgeMetadata { nb_taken });
+    }
+
+    edges
+}
+
+/// Write the threads chunk.
+pub fn write_threads<W>(writer: &mut W, threads: &[AlcovThread]) -> Result<(), Error>
+where
+    W: Write,
+{
+    for thread in threads {
+        thread.write(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Read the threads chunk.
+pub fn read_threads(threads_buf: &[u8]) -> Result<Vec<AlcovThread>, Error> {
+    let mut threads_rdr = Cursor::new(threads_buf);
+    let mut threads: Vec<AlcovThread> = Vec::new();
+
+    while threads_rdr.position() < threads_buf.len() as u64 {
+        threads.push(AlcovThread::read(&mut threads_rdr)?);
+    }
+
+    Ok(threads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v0::{AlcovBlock, AlcovHeader, AlcovModule, AlcovSegment};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_threads() {
+        let modules = vec![AlcovModule::new(0, None, vec![AlcovSegment::new(0..0x1000)]).unwrap()];
+        let blocks = vec![
+            AlcovBlock::new(0, 0, 0x10, 4, 3),
+            AlcovBlock::new(0, 0, 0x20, 8, 2),
+            AlcovBlock::new(0, 0, 0x30, 8, 1),
+        ];
+        let mut edges = AlcovEdges::new();
+        edges.add(&blocks, 0, 1).unwrap();
+        edges.add(&blocks, 0, 2).unwrap();
+        edges.add(&blocks, 1, 0).unwrap();
+        edges.add(&blocks, 2, 0).unwrap();
+
+        let mut main = AlcovThread::new(100, Some(String::from("server")));
+        main.add_block(0, 2);
+        main.add_block(1, 2);
+        main.add_edge(0, 1, 1);
+        main.add_edge(1, 0, 1);
+        let mut worker = AlcovThread::new(101, None);
+        worker.add_block(0, 1);
+        worker.add_block(2, 1);
+        worker.add_edge(0, 2, 1);
+        worker.add_edge(2, 0, 1);
+
+        let mut alcov = Alcov::new(
+            AlcovHeader::new(None::<PathBuf>, true),
+            modules,
+            blocks,
+            Some(edges),
+        );
+        alcov.threads = vec![main, worker];
+
+        let mut out_buf: Vec<u8> = Vec::new();
+        alcov.write(&mut out_buf).unwrap();
+        assert_eq!(Alcov::read(&mut Cursor::new(out_buf)).unwrap(), alcov);
+
+        let projected = alcov.project_thread(101).unwrap();
+        let nb_taken: Vec<u64> = projected
+            .blocks
+            .iter()
+            .map(|block| block.nb_taken)
+            .collect();
+        assert_eq!(nb_taken, [1, 0, 1]);
+        assert_eq!(projected.edges.as_ref().unwrap().nb_edges(), 2);
+        assert!(matches!(
+            alcov.project_thread(102),
+            Err(Error::UnknownThread(102))
+        ));
+
+        let mut aggregated = alcov.clone();
+        aggregated
+            .blocks
+            .iter_mut()
+            .for_each(|block| block.nb_taken = 0);
+        aggregated.edges = Some(AlcovEdges::new());
+        aggregated.aggregate_threads().unwrap();
+        assert_eq!(aggregated, alcov);
+
+        aggregated.threads[1].add_block(3, 1);
+        assert!(matches!(
+            aggregated.aggregate_threads(),
+            Err(Error::ThreadWithoutBlock {
+                tid: 101,
+                block_id: 3
+            })
+        ));
+    }
+}
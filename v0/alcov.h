@@ -27,27 +27,128 @@
 const uint64_t ALCOV_MAGIC = 0xdda28f766f636c61;
 
 const uint64_t ALCOV_VERSION_MAJOR = 0;
-const uint64_t ALCOV_VERSION_MINOR = 1;
+const uint64_t ALCOV_VERSION_MINOR = 12;
 
 // if this flag is set, alcov_blocks.edge_offset and alcov_blocks.nb_edges are defined according to their definition.
 // otherwise, their value is undefined.
 #define ALCOV_FLAG_EDGES 		(1 << 0) // edge coverage is available.
-#define ALCOV_FLAG_COMPRESS		(1 << 1) // block section (and edge section if enabled) are compressed using LZMA2.
+#define ALCOV_FLAG_COMPRESS		(1 << 1) // at least one chunk is compressed. the codec of each chunk is given in the chunk table.
 #define ALCOV_FLAG_INPUT_PATH	(1 << 2) // the first path in the path chunk is the path to the input for which we are measuring coverage.
+#define ALCOV_FLAG_RAW_PATHS	(1 << 3) // paths are raw bytes, as given by the system. otherwise, paths are ASCII.
+
+// compression codecs of chunks.
+#define ALCOV_CODEC_NONE		0 // the chunk is stored as is.
+#define ALCOV_CODEC_LZMA2		1 // the chunk is compressed using LZMA2.
+#define ALCOV_CODEC_ZSTD		2 // the chunk is compressed as a single zstd frame.
+#define ALCOV_CODEC_LZ4			3 // the chunk is compressed as a single LZ4 block (without frame).
+
+// types of chunks.
+#define ALCOV_CHUNK_MODULES		0 // array of alcov_module.
+#define ALCOV_CHUNK_PATHS		1 // NULL-terminated paths (ASCII, or raw bytes if the RAW_PATHS flag is set).
+#define ALCOV_CHUNK_BLOCKS		2 // array of alcov_block.
+#define ALCOV_CHUNK_EDGES		3 // array of alcov_out_edge. only present if the EDGES flag is set.
+#define ALCOV_CHUNK_BLOCK_FRAMES	4 // alcov_frame_index. if present, the blocks chunk is split in independently compressed frames.
+#define ALCOV_CHUNK_CHECKSUMS	5 // alcov_checksums. optional integrity section, right after the header.
+#define ALCOV_CHUNK_MODULE_IDS	6 // alcov_module_id entries, until the end of the chunk. optional identities of the files backing modules.
+#define ALCOV_CHUNK_MODULE_INFO	7 // one alcov_module_info per module. optional architecture of modules, and file offsets and permissions of segments.
+#define ALCOV_CHUNK_BLOCK_INFO	8 // one alcov_block_info per block. optional instruction encoding and number of instructions of blocks.
+#define ALCOV_CHUNK_METADATA	9 // alcov_metadata_entry entries, until the end of the chunk. optional free-form metadata.
+#define ALCOV_CHUNK_INPUT		10 // alcov_input. optional content of the input given to the program.
+#define ALCOV_CHUNK_MODULE_CODE	11 // alcov_module_code entries, until the end of the chunk. optional code snapshots of modules.
+#define ALCOV_CHUNK_THREADS		12 // alcov_thread entries, until the end of the chunk. optional per-thread coverage.
+
+// checksum algorithms of the integrity section.
+#define ALCOV_CHECKSUM_CRC32C	0 // CRC-32C (Castagnoli), zero-extended to 64 bits.
+#define ALCOV_CHECKSUM_XXH64	1 // XXH64, with a seed of 0.
+
+// kinds of module identities.
+#define ALCOV_MODULE_ID_GNU_BUILD_ID	0 // content of the NT_GNU_BUILD_ID note of an ELF file.
+#define ALCOV_MODULE_ID_PE			1 // TimeDateStamp and SizeOfImage of a PE file, as two uint32_t.
+#define ALCOV_MODULE_ID_SHA256		2 // SHA-256 of the whole file.
+
+// types of metadata values.
+#define ALCOV_VALUE_STRING		0 // UTF-8 string, without NULL terminator.
+#define ALCOV_VALUE_INT			1 // int64_t.
+#define ALCOV_VALUE_UINT		2 // uint64_t.
+#define ALCOV_VALUE_BOOL		3 // uint8_t, 0 or 1.
+#define ALCOV_VALUE_BYTES		4 // raw bytes.
+#define ALCOV_VALUE_TIMESTAMP	5 // uint64_t, nanoseconds since the UNIX epoch.
+#define ALCOV_VALUE_STRINGS		6 // list of NULL-terminated UTF-8 strings.
+
+// instruction set families (alcov_module_info.isa).
+#define ALCOV_ISA_UNKNOWN		0 // the architecture of the module is unknown.
+#define ALCOV_ISA_X86			1 // x86 (32 bits) or x86-64 (64 bits).
+#define ALCOV_ISA_ARM			2 // ARM (32 bits) or AArch64 (64 bits).
+#define ALCOV_ISA_MIPS			3
+#define ALCOV_ISA_POWERPC		4
+#define ALCOV_ISA_RISCV			5
+
+// endianness (alcov_module_info.endianness).
+#define ALCOV_ENDIAN_LITTLE		0
+#define ALCOV_ENDIAN_BIG		1
+
+// instruction encodings, for instruction sets with several of them (alcov_module_info.mode).
+#define ALCOV_MODE_DEFAULT		0 // the only or main encoding of the instruction set.
+#define ALCOV_MODE_THUMB		1 // ARM Thumb / Thumb-2.
+#define ALCOV_MODE_MIPS16		2 // MIPS16e.
+#define ALCOV_MODE_MICROMIPS	3 // microMIPS.
+#define ALCOV_MODE_UNKNOWN		0xff // only in alcov_block_info: the block is in the default mode of its module.
+
+// permissions of segments (alcov_segment_info.perms). no permission means they are unknown.
+#define ALCOV_PERM_READ			(1 << 0)
+#define ALCOV_PERM_WRITE		(1 << 1)
+#define ALCOV_PERM_EXECUTE		(1 << 2)
+
+// alcov_segment_info.file_offset of a segment without known file offset.
+const uint64_t ALCOV_NO_FILE_OFFSET = 0xffffffffffffffff;
+
+// entry of the chunk table.
+struct __attribute__((packed)) alcov_chunk {
+	uint16_t chunk_type;				// type of the chunk (ALCOV_CHUNK_*).
+	uint8_t codec;						// codec used to compress the chunk (ALCOV_CODEC_*).
+	uint64_t offset;					// offset of the chunk in file.
+	uint64_t size;						// size of the chunk in file, in bytes.
+	uint64_t raw_size;					// size of the chunk once decompressed, in bytes.
+};
+
+// frame of the blocks chunk.
+struct __attribute__((packed)) alcov_frame {
+	uint64_t offset;					// offset of the frame from the start of the blocks chunk.
+	uint64_t size;						// size of the compressed frame, in bytes.
+};
+
+// index of the frames of the blocks chunk.
+struct __attribute__((packed)) alcov_frame_index {
+	uint64_t nb_blocks_per_frame;		// number of blocks in each frame, except the last one which can be smaller.
+	uint64_t nb_frames;					// number of frames in next array.
+	struct alcov_frame frames[];		// frames, in the order of their blocks.
+};
+
+// checksum of a chunk.
+struct __attribute__((packed)) alcov_chunk_checksum {
+	uint16_t chunk_type;				// type of the chunk (ALCOV_CHUNK_*).
+	uint64_t checksum;					// checksum of the chunk, as stored in file (i.e. compressed).
+};
+
+// integrity section.
+struct __attribute__((packed)) alcov_checksums {
+	uint8_t algorithm;					// checksum algorithm (ALCOV_CHECKSUM_*).
+	uint64_t file_checksum;				// checksum of the file, from its start to the end of the last chunk, without this chunk.
+	uint16_t nb_chunks;					// number of checksums in next array.
+	struct alcov_chunk_checksum chunks[];	// checksums of every other chunk.
+};
 
 // header of alcov.
 struct __attribute__((packed)) alcov_hdr {
 	uint64_t magic; 					// equals ALCOV_MAGIC, always the same across every version.
 	uint64_t version_major; 			// equals ALCOV_VERSION_MAJOR, increases when the specification changes in a significant way.
 	uint64_t version_minor; 			// equals ALCOV_VERSION_MINOR, increases when the specification changes include minor breaking changes.
-	uint16_t nb_modules;				// number of blocks used during coverage.
-	uint64_t nb_blocks; 				// number of modules.
+	uint16_t nb_modules;				// number of modules.
+	uint64_t nb_blocks; 				// number of blocks used during coverage.
 	uint64_t nb_edges;					// number of edges.
-	uint64_t modules_start;				// offset of modules chunk in file.
-	uint64_t paths_start;				// offset of paths chunk in file.
-	uint64_t blocks_start;				// offset of blocks chunk in file.
-	uint64_t edges_start;				// offset of edges chunk in file.
 	uint16_t flags;						// optional flags.
+	uint16_t nb_chunks;					// number of entries in the chunk table.
+	struct alcov_chunk chunks[];		// chunk table, sorted by offset.
 };
 
 struct __attribute__((packed)) alcov_segment {
@@ -62,6 +163,28 @@ struct __attribute__((packed)) alcov_module {
 	struct alcov_segment segments[];	// Segments in the modules.
 };
 
+struct __attribute__((packed)) alcov_segment_info {
+	uint64_t file_offset;				// offset of the segment in the file backing the module, or ALCOV_NO_FILE_OFFSET.
+	uint8_t perms;						// permissions of the segment (ALCOV_PERM_*).
+};
+
+// information about a module, in the module info chunk.
+struct __attribute__((packed)) alcov_module_info {
+	uint8_t isa;						// instruction set family (ALCOV_ISA_*). if unknown, the next fields are ignored.
+	uint8_t bits;						// 16, 32 or 64.
+	uint8_t endianness;					// ALCOV_ENDIAN_*.
+	uint8_t mode;						// default instruction encoding of the module (ALCOV_MODE_*).
+	struct alcov_segment_info segments[];	// one per segment of the module, in order.
+};
+
+// identity of the file backing a module.
+struct __attribute__((packed)) alcov_module_id {
+	uint16_t module_id;					// module identified.
+	uint8_t kind;						// kind of identity (ALCOV_MODULE_ID_*).
+	uint8_t size;						// size of data.
+	uint8_t data[];						// identity, depending on its kind.
+};
+
 struct __attribute__((packed)) alcov_block {
 	uint64_t segment_offset;			// the block offset in its segment.
 	uint32_t size;						// the size of the block.
@@ -72,6 +195,56 @@ struct __attribute__((packed)) alcov_block {
 	uint64_t nb_taken;					// the number of times the block has been traversed. 0 means it was not measured and this number is unknown.
 };
 
+// metadata entry, in the metadata chunk.
+struct __attribute__((packed)) alcov_metadata_entry {
+	uint16_t key_size;					// size of the key in bytes.
+	uint8_t value_type;					// type of the value (ALCOV_VALUE_*).
+	uint32_t value_size;				// size of the value in bytes.
+	uint8_t data[];						// the key (UTF-8, without NULL terminator), followed by the value.
+};
+
+// content of the input, in the input chunk.
+struct __attribute__((packed)) alcov_input {
+	uint8_t sha256[32];					// SHA-256 of the input.
+	uint8_t data[];						// the input, until the end of the chunk.
+};
+
+// snapshot of the code of a module, in the module code chunk.
+struct __attribute__((packed)) alcov_module_code {
+	uint16_t module_id;					// the module the code belongs to.
+	uint64_t size;						// size of the snapshot in bytes.
+	uint8_t data[];						// bytes of the module, from its base address.
+};
+
+// coverage of a thread, in the threads chunk.
+// it is followed by nb_blocks alcov_thread_block, then by nb_edges alcov_thread_edge.
+struct __attribute__((packed)) alcov_thread {
+	uint64_t tid;						// thread id, as given by the operating system.
+	uint64_t nb_blocks;					// number of blocks executed by the thread.
+	uint64_t nb_edges;					// number of edges taken by the thread.
+	uint16_t name_size;					// size of the name of the thread, 0 if unknown.
+	char name[];						// name of the thread (UTF-8, not NULL-terminated).
+};
+
+// hit count of a block by a thread.
+struct __attribute__((packed)) alcov_thread_block {
+	uint64_t block_id;					// index of the block in the blocks chunk.
+	uint64_t nb_taken;					// number of times the thread executed the block.
+};
+
+// hit count of an edge by a thread.
+struct __attribute__((packed)) alcov_thread_edge {
+	uint64_t src_block_id;				// index of the source block in the blocks chunk.
+	uint64_t dst_block_id;				// index of the destination block in the blocks chunk.
+	uint64_t nb_taken;					// number of times the thread took the edge.
+};
+
+// information about a block, in the block info chunk.
+struct __attribute__((packed)) alcov_block_info {
+	uint8_t mode;						// instruction encoding of the block (ALCOV_MODE_*), or ALCOV_MODE_UNKNOWN.
+	uint32_t nb_instructions;			// number of instructions of the block. 0 means it is unknown.
+};
+
 struct __attribute__((packed)) alcov_out_edge {
 	uint64_t dst_block_id; 				// the id of the outgoing block. dst block is implicitly determined by parsing alcov_blocks.
 	uint64_t nb_taken; 					// the number of times the edge has been taken. 0 means it was not measured and this number is unknown.